@@ -15,6 +15,17 @@ const ERRORS = {
   WriteType: 13,
   CloseUnknown: 14,
   CloseType: 15,
+  ShowOpenFilePickerUnknown: 16,
+  ShowOpenFilePickerAbort: 17,
+  ShowOpenFilePickerSecurity: 18,
+  ShowOpenFilePickerType: 19,
+  GetFileUnknown: 20,
+  GetFileNotAllowed: 21,
+  GetFileNotFound: 22,
+  ReadUnknown: 23,
+  ReadNotReadable: 24,
+  RequestPermissionUnknown: 25,
+  RequestPermissionSecurity: 26,
 };
 const SHOW_SAVE_FILE_PICKER_ERRORS = {
   Unknown: ERRORS.ShowSaveUnknown,
@@ -22,6 +33,21 @@ const SHOW_SAVE_FILE_PICKER_ERRORS = {
   SecurityError: ERRORS.ShowSaveFilePickerSecurity,
   TypeError: ERRORS.ShowSaveFilePickerType,
 };
+const SHOW_OPEN_FILE_PICKER_ERRORS = {
+  Unknown: ERRORS.ShowOpenFilePickerUnknown,
+  AbortError: ERRORS.ShowOpenFilePickerAbort,
+  SecurityError: ERRORS.ShowOpenFilePickerSecurity,
+  TypeError: ERRORS.ShowOpenFilePickerType,
+};
+const GET_FILE_ERRORS = {
+  Unknown: ERRORS.GetFileUnknown,
+  NotAllowedError: ERRORS.GetFileNotAllowed,
+  NotFoundError: ERRORS.GetFileNotFound,
+};
+const READ_ERRORS = {
+  Unknown: ERRORS.ReadUnknown,
+  NotReadableError: ERRORS.ReadNotReadable,
+};
 const CREATE_WRITABLE_ERRORS = {
   Unknown: ERRORS.CreateWritableUnknown,
   NotAllowedError: ERRORS.CreateWritableNotAllowed,
@@ -32,9 +58,13 @@ const CREATE_WRITABLE_ERRORS = {
 const WRITE_ERRORS = {
   Unknown: ERRORS.WriteUnknown,
   NotAllowedError: ERRORS.WriteNotAllowed,
-  QuotaExceededError: ERRORS.QuotaExceeded,
+  QuotaExceededError: ERRORS.WriteQuotaExceeded,
   TypeError: ERRORS.WriteType,
 };
+const REQUEST_PERMISSION_ERRORS = {
+  Unknown: ERRORS.RequestPermissionUnknown,
+  SecurityError: ERRORS.RequestPermissionSecurity,
+};
 const CLOSE_ERRORS = {
   Unknown: ERRORS.CloseUnknown,
   TypeError: ERRORS.CloseType,
@@ -48,14 +78,50 @@ function matchErr(errors, err) {
   return errors.Unknown;
 }
 
-async function showSaveFilePicker() {
+async function showSaveFilePicker(suggestedName) {
   try {
-    return await window.showSaveFilePicker();
+    return await window.showSaveFilePicker({ suggestedName });
   } catch (err) {
     throw matchErr(SHOW_SAVE_FILE_PICKER_ERRORS, err);
   }
 }
 
+async function showOpenFilePicker() {
+  try {
+    const [handle] = await window.showOpenFilePicker({
+      types: [
+        {
+          description: "Bookmarks",
+          accept: {
+            "application/json": [".json"],
+            "text/html": [".html", ".htm"],
+            "text/csv": [".csv"],
+          },
+        },
+      ],
+    });
+    return handle;
+  } catch (err) {
+    throw matchErr(SHOW_OPEN_FILE_PICKER_ERRORS, err);
+  }
+}
+
+async function getFile(handle) {
+  try {
+    return await handle.getFile();
+  } catch (err) {
+    throw matchErr(GET_FILE_ERRORS, err);
+  }
+}
+
+async function read(file) {
+  try {
+    return await file.text();
+  } catch (err) {
+    throw matchErr(READ_ERRORS, err);
+  }
+}
+
 async function createWritable(handle) {
   try {
     return await handle.createWritable();
@@ -80,9 +146,9 @@ async function close(stream) {
   }
 }
 
-export async function save_to_file(handle, file_data) {
+export async function save_to_file(handle, file_data, suggested_name) {
   if (!handle) {
-    handle = await showSaveFilePicker();
+    handle = await showSaveFilePicker(suggested_name);
   }
 
   let stream = await createWritable(handle);
@@ -92,3 +158,33 @@ export async function save_to_file(handle, file_data) {
 
   return handle;
 }
+
+export async function open_from_file() {
+  let handle = await showOpenFilePicker();
+  let file = await getFile(handle);
+
+  return await read(file);
+}
+
+export async function read_from_handle(handle) {
+  let file = await getFile(handle);
+
+  return { file_data: await read(file), last_modified: file.lastModified };
+}
+
+export async function verify_permission(handle, request) {
+  const options = { mode: "readwrite" };
+
+  if ((await handle.queryPermission(options)) === "granted") {
+    return true;
+  }
+  if (!request) {
+    return false;
+  }
+
+  try {
+    return (await handle.requestPermission(options)) === "granted";
+  } catch (err) {
+    throw matchErr(REQUEST_PERMISSION_ERRORS, err);
+  }
+}
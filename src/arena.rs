@@ -16,11 +16,7 @@ mod id {
 
     impl<T> Clone for ArenaId<T> {
         fn clone(&self) -> Self {
-            Self {
-                id: self.id,
-                generation: self.generation,
-                _marker: self._marker,
-            }
+            *self
         }
     }
     impl<T> Copy for ArenaId<T> {}
@@ -38,7 +34,7 @@ mod id {
     }
     impl<T> PartialOrd for ArenaId<T> {
         fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-            Some(self.id.cmp(&other.id))
+            Some(self.cmp(other))
         }
     }
     impl<T> Ord for ArenaId<T> {
@@ -120,8 +116,6 @@ impl<T> Arena<T> {
         self.count += 1;
         match self.removed_entries.pop() {
             Some(RemovedEntry { index, generation }) => {
-                assert!(index <= const { u32::MAX });
-
                 let entry = &mut self.entries[index as usize];
 
                 let generation = generation + 1;
@@ -132,7 +126,7 @@ impl<T> Arena<T> {
                 };
 
                 ArenaId {
-                    id: index as u32,
+                    id: index,
                     generation,
                     _marker: std::marker::PhantomData,
                 }
@@ -156,9 +150,9 @@ impl<T> Arena<T> {
         }
     }
     pub fn remove(&mut self, id: ArenaId<T>) {
-        self.count -= 1;
         let entry = &mut self.entries[id.id as usize];
         if id.generation == entry.generation {
+            self.count -= 1;
             // Runs drop of T
             unsafe { entry.value.assume_init_drop() }; // safe because generations match
             self.removed_entries.push(RemovedEntry {
@@ -171,8 +165,9 @@ impl<T> Arena<T> {
     pub fn entry<'a>(&'a self, id: ArenaId<T>) -> EntryRef<'a, T> {
         self.try_entry(id).expect("Entry should exist in the arena")
     }
+    /// None if the id isn't in this arena, like one of another arena
     pub fn try_entry<'a>(&'a self, id: ArenaId<T>) -> Option<EntryRef<'a, T>> {
-        let entry = &self.entries[id.id as usize];
+        let entry = self.entries.get(id.id as usize)?;
         if id.generation == entry.generation {
             Some(EntryRef {
                 value: unsafe { entry.value.assume_init_ref() }, // safe because generations match
@@ -187,7 +182,7 @@ impl<T> Arena<T> {
             .expect("Entry should exist in the arena")
     }
     pub fn try_entry_mut<'a>(&'a mut self, id: ArenaId<T>) -> Option<EntryMut<'a, T>> {
-        let entry = &mut self.entries[id.id as usize];
+        let entry = self.entries.get_mut(id.id as usize)?;
         if id.generation == entry.generation {
             Some(EntryMut {
                 value: unsafe { entry.value.assume_init_mut() }, // safe because generations match
@@ -236,7 +231,7 @@ mod iter {
         type Item = EntryRef<'a, T>;
 
         fn next(&mut self) -> Option<Self::Item> {
-            while let Some(entry) = self.entries.next() {
+            for entry in self.entries.by_ref() {
                 if entry.generation == Arena::<T>::TOMBSTONE {
                     continue;
                 }
@@ -260,7 +255,7 @@ mod iter {
         type Item = EntryMut<'a, T>;
 
         fn next(&mut self) -> Option<Self::Item> {
-            while let Some(entry) = self.entries.next() {
+            for entry in self.entries.by_ref() {
                 if entry.generation == Arena::<T>::TOMBSTONE {
                     continue;
                 }
@@ -284,7 +279,7 @@ mod iter {
         type Item = ArenaId<T>;
 
         fn next(&mut self) -> Option<Self::Item> {
-            while let Some((index, entry)) = self.entries.next() {
+            for (index, entry) in self.entries.by_ref() {
                 if entry.generation == Arena::<T>::TOMBSTONE {
                     continue;
                 }
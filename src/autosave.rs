@@ -0,0 +1,70 @@
+//! Writes the library to the file [`Action::Sync`] remembered, once changes have settled.
+
+use dioxus::prelude::*;
+
+use crate::{
+    button::{Button, ButtonSize},
+    time::{self, Timestamp, SECOND},
+    Action,
+};
+
+/// How long there must be no actions before changes are written
+pub const AUTOSAVE_DELAY: Timestamp = 2 * SECOND;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaveStatus {
+    /// Nothing written or changed since the app started
+    Idle,
+    /// Autosave is on but there is no file yet, sync picks one
+    NoFile,
+    Unsaved,
+    Saved(Timestamp),
+    /// Permission to write lapsed after a reload, asking again needs a click
+    NeedsPermission,
+    /// The file was changed elsewhere and the changes conflict, see [`crate::conflict_dialog`]
+    Conflicted,
+    Failed,
+}
+
+#[component]
+pub fn AutosaveStatus(enabled: bool, status: SaveStatus) -> Element {
+    let coroutine = use_coroutine_handle::<Action>();
+
+    let message = match status {
+        SaveStatus::Idle => None,
+        SaveStatus::NoFile => Some("Sync once to choose a file".to_string()),
+        SaveStatus::Unsaved => Some("Unsaved changes".to_string()),
+        SaveStatus::Saved(at) => Some(format!("Saved {}", time::format(at))),
+        SaveStatus::NeedsPermission => Some("Saving needs permission".to_string()),
+        SaveStatus::Conflicted => Some("Changed elsewhere, sync to merge".to_string()),
+        SaveStatus::Failed => Some("Couldn't save".to_string()),
+    };
+
+    rsx! {
+        label {
+            class: "mr-4 flex items-center gap-2 text-sm select-none",
+            input {
+                r#type: "checkbox",
+                checked: enabled,
+                onchange: move |ev| coroutine.send(Action::SetAutosave { enabled: ev.checked() }),
+            }
+            "Autosave"
+        }
+        if let Some(message) = message {
+            span {
+                class: "mr-4 text-sm text-gray-500",
+                "{message}"
+            }
+        }
+        if status == SaveStatus::NeedsPermission {
+            span {
+                class: "mr-8",
+                Button {
+                    value: "Allow",
+                    size: ButtonSize::Small,
+                    onclick: move |_| coroutine.send(Action::Autosave { request: true }),
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,9 @@
+/// Growable bitset. Bits past `len` are treated as unset, so fields of different lengths can be
+/// combined and compared.
+#[derive(Clone, Debug)]
 pub struct BitField {
     inner: Vec<usize>,
+    /// In bits
     len: usize,
 }
 
@@ -9,38 +13,132 @@ impl BitField {
     pub fn new() -> Self {
         Self::init(1024)
     }
+    /// `len` unset bits
     pub fn init(len: usize) -> Self {
-        let len = len.div_ceil(Self::SIZE);
         Self {
-            inner: vec![0; len],
+            inner: vec![0; len.div_ceil(Self::SIZE)],
             len,
         }
     }
-    pub fn reserve_init(&mut self, len: usize) {
-        let len = len.div_ceil(Self::SIZE);
-        self.inner.reserve_exact(len);
-
-        for _ in 0..len {
-            self.inner.push(0);
-        }
+    /// Appends `additional` unset bits
+    pub fn reserve_init(&mut self, additional: usize) {
+        self.grow(self.len + additional);
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
-    // Panics if not reserved
+    /// Grows when setting past the end
     pub fn set(&mut self, index: usize, value: bool) {
-        let index = index.div_ceil(Self::SIZE);
-        let remainder = index % Self::SIZE;
-        let bytes = &mut self.inner[index];
-        *bytes = *bytes & !((1 << remainder) | ((value as usize) << remainder));
+        if index >= self.len {
+            if !value {
+                return;
+            }
+            self.grow(index + 1);
+        }
+
+        let (word, remainder) = Self::position(index);
+        let bits = &mut self.inner[word];
+        if value {
+            *bits |= 1 << remainder;
+        } else {
+            *bits &= !(1 << remainder);
+        }
     }
-    // Panics if not reserved
-    pub fn get(&mut self, index: usize) -> bool {
-        let index = index.div_ceil(Self::SIZE);
-        let remainder = index % Self::SIZE;
+    /// Out of range bits are unset
+    pub fn get(&self, index: usize) -> bool {
+        if index >= self.len {
+            return false;
+        }
 
-        self.inner[index] >> remainder == 1
+        let (word, remainder) = Self::position(index);
+        (self.inner[word] >> remainder) & 1 == 1
+    }
+    pub fn clear(&mut self, index: usize) {
+        self.set(index, false);
+    }
+    /// Returns the new value, grows when toggling past the end
+    pub fn toggle(&mut self, index: usize) -> bool {
+        let value = !self.get(index);
+        self.set(index, value);
+        value
+    }
+    /// Unsets every bit, keeps the length
+    pub fn clear_all(&mut self) {
+        self.inner.fill(0);
+    }
+    /// Keeps bits that are set in both
+    pub fn and(&mut self, other: &BitField) {
+        self.grow(other.len);
+        for (index, bits) in self.inner.iter_mut().enumerate() {
+            *bits &= other.inner.get(index).copied().unwrap_or(0);
+        }
+    }
+    /// Keeps bits that are set in either
+    pub fn or(&mut self, other: &BitField) {
+        self.grow(other.len);
+        for (bits, other) in self.inner.iter_mut().zip(other.inner.iter()) {
+            *bits |= other;
+        }
+    }
+    /// Keeps bits that are set in only one of them
+    pub fn xor(&mut self, other: &BitField) {
+        self.grow(other.len);
+        for (bits, other) in self.inner.iter_mut().zip(other.inner.iter()) {
+            *bits ^= other;
+        }
+    }
+    /// Flips every bit up to `len`, bits past the end stay unset
+    pub fn not(&mut self) {
+        for bits in self.inner.iter_mut() {
+            *bits = !*bits;
+        }
+        self.clear_tail();
+    }
+    pub fn count_ones(&self) -> usize {
+        self.inner.iter().map(|x| x.count_ones() as usize).sum()
+    }
+    /// Indices of set bits in ascending order
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.inner.iter().enumerate().flat_map(|(word, bits)| {
+            let mut bits = *bits;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let remainder = bits.trailing_zeros() as usize;
+                // Clears the lowest set bit
+                bits &= bits - 1;
+                Some(word * Self::SIZE + remainder)
+            })
+        })
     }
     pub fn iter_fields(&self) -> impl Iterator<Item = Field> + '_ {
         self.inner.iter().map(|x| Field { inner: *x })
     }
+    fn position(index: usize) -> (usize, usize) {
+        (index / Self::SIZE, index % Self::SIZE)
+    }
+    /// Never shrinks
+    fn grow(&mut self, len: usize) {
+        if len <= self.len {
+            return;
+        }
+        self.len = len;
+        self.inner.resize(len.div_ceil(Self::SIZE), 0);
+    }
+    /// Unsets bits of the last word that are past `len`
+    fn clear_tail(&mut self) {
+        let remainder = self.len % Self::SIZE;
+        if remainder == 0 {
+            return;
+        }
+        if let Some(bits) = self.inner.last_mut() {
+            *bits &= (1 << remainder) - 1;
+        }
+    }
 }
 
 impl Default for BitField {
@@ -49,10 +147,36 @@ impl Default for BitField {
     }
 }
 
+/// Equal when the same bits are set, length is ignored
+impl PartialEq for BitField {
+    fn eq(&self, other: &Self) -> bool {
+        let (short, long) = if self.inner.len() <= other.inner.len() {
+            (&self.inner, &other.inner)
+        } else {
+            (&other.inner, &self.inner)
+        };
+
+        short == &long[..short.len()] && long[short.len()..].iter().all(|x| *x == 0)
+    }
+}
+impl Eq for BitField {}
+
 pub struct Field {
     inner: usize,
 }
 
+impl IntoIterator for Field {
+    type Item = bool;
+    type IntoIter = FieldIterator;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FieldIterator {
+            field: self.inner,
+            index: 0,
+        }
+    }
+}
+
 pub struct FieldIterator {
     field: usize,
     index: usize,
@@ -63,7 +187,7 @@ impl Iterator for FieldIterator {
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.index < usize::BITS as usize {
-            let value = self.index << 1 == 1;
+            let value = (self.field >> self.index) & 1 == 1;
             self.index += 1;
             Some(value)
         } else {
@@ -71,3 +195,313 @@ impl Iterator for FieldIterator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::BitField;
+
+    const SIZE: usize = BitField::SIZE;
+
+    fn from_ones(len: usize, ones: &[usize]) -> BitField {
+        let mut field = BitField::init(len);
+        for index in ones {
+            field.set(*index, true);
+        }
+        field
+    }
+
+    fn ones(field: &BitField) -> Vec<usize> {
+        field.iter_ones().collect()
+    }
+
+    #[test]
+    fn init_is_unset() {
+        let field = BitField::init(SIZE * 3 + 5);
+        assert_eq!(field.len(), SIZE * 3 + 5);
+        assert_eq!(field.count_ones(), 0);
+        for index in 0..field.len() {
+            assert!(!field.get(index));
+        }
+    }
+
+    #[test]
+    fn default_is_empty() {
+        let field = BitField::default();
+        assert!(field.is_empty());
+        assert_eq!(field.len(), 0);
+        assert!(!field.get(0));
+        assert_eq!(field.iter_ones().next(), None);
+    }
+
+    #[test]
+    fn new_has_default_length() {
+        assert_eq!(BitField::new().len(), 1024);
+    }
+
+    #[test]
+    fn set_and_get_every_bit() {
+        let len = SIZE * 2 + 7;
+        for index in 0..len {
+            let mut field = BitField::init(len);
+            field.set(index, true);
+
+            for other in 0..len {
+                assert_eq!(field.get(other), other == index, "set {index}, get {other}");
+            }
+            assert_eq!(field.count_ones(), 1);
+        }
+    }
+
+    #[test]
+    fn set_false_unsets_only_that_bit() {
+        let len = SIZE * 2;
+        let mut field = BitField::init(len);
+        for index in 0..len {
+            field.set(index, true);
+        }
+
+        field.set(SIZE - 1, false);
+        field.set(SIZE, false);
+
+        assert!(!field.get(SIZE - 1));
+        assert!(!field.get(SIZE));
+        assert!(field.get(SIZE - 2));
+        assert!(field.get(SIZE + 1));
+        assert_eq!(field.count_ones(), len - 2);
+    }
+
+    #[test]
+    fn set_is_idempotent() {
+        let mut field = BitField::init(8);
+        field.set(3, true);
+        field.set(3, true);
+        assert_eq!(ones(&field), vec![3]);
+
+        field.set(3, false);
+        field.set(3, false);
+        assert_eq!(field.count_ones(), 0);
+    }
+
+    #[test]
+    fn set_grows_on_demand() {
+        let mut field = BitField::default();
+        field.set(SIZE * 4 + 1, true);
+
+        assert_eq!(field.len(), SIZE * 4 + 2);
+        assert!(field.get(SIZE * 4 + 1));
+        assert_eq!(ones(&field), vec![SIZE * 4 + 1]);
+    }
+
+    #[test]
+    fn unsetting_past_the_end_does_not_grow() {
+        let mut field = BitField::init(4);
+        field.set(SIZE * 8, false);
+        field.clear(SIZE * 8);
+        assert_eq!(field.len(), 4);
+    }
+
+    #[test]
+    fn get_past_the_end_is_unset() {
+        let field = from_ones(3, &[0, 1, 2]);
+        assert!(!field.get(3));
+        assert!(!field.get(SIZE));
+        assert!(!field.get(usize::MAX));
+    }
+
+    #[test]
+    fn clear() {
+        let mut field = from_ones(10, &[1, 5, 9]);
+        field.clear(5);
+        assert_eq!(ones(&field), vec![1, 9]);
+    }
+
+    #[test]
+    fn clear_all_keeps_length() {
+        let mut field = from_ones(SIZE + 10, &[1, SIZE + 9]);
+        field.clear_all();
+        assert_eq!(field.count_ones(), 0);
+        assert_eq!(field.len(), SIZE + 10);
+    }
+
+    #[test]
+    fn toggle() {
+        let mut field = BitField::init(4);
+        assert!(field.toggle(2));
+        assert!(field.get(2));
+        assert!(!field.toggle(2));
+        assert!(!field.get(2));
+    }
+
+    #[test]
+    fn toggle_grows_on_demand() {
+        let mut field = BitField::init(4);
+        assert!(field.toggle(SIZE + 3));
+        assert_eq!(field.len(), SIZE + 4);
+        assert!(field.get(SIZE + 3));
+    }
+
+    #[test]
+    fn reserve_init_appends_bits() {
+        let mut field = from_ones(SIZE - 1, &[SIZE - 2]);
+        field.reserve_init(SIZE + 1);
+
+        assert_eq!(field.len(), SIZE * 2);
+        assert_eq!(ones(&field), vec![SIZE - 2]);
+
+        field.set(SIZE * 2 - 1, true);
+        assert_eq!(field.len(), SIZE * 2);
+    }
+
+    #[test]
+    fn count_ones() {
+        let field = from_ones(SIZE * 3, &[0, 1, SIZE, SIZE * 2 + 1, SIZE * 3 - 1]);
+        assert_eq!(field.count_ones(), 5);
+    }
+
+    #[test]
+    fn iter_ones_is_ascending_across_words() {
+        let indices = [0, 3, SIZE - 1, SIZE, SIZE + 1, SIZE * 2 + 10, SIZE * 3 - 1];
+        let field = from_ones(
+            SIZE * 3,
+            &[SIZE * 3 - 1, 0, SIZE, 3, SIZE * 2 + 10, SIZE - 1, SIZE + 1],
+        );
+        assert_eq!(ones(&field), indices);
+    }
+
+    #[test]
+    fn iter_ones_all_set() {
+        let len = SIZE * 2;
+        let mut field = BitField::init(len);
+        field.not();
+        assert_eq!(ones(&field), (0..len).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn and() {
+        let mut a = from_ones(SIZE * 2, &[1, 2, SIZE + 1]);
+        let b = from_ones(SIZE * 2, &[2, 3, SIZE + 1]);
+        a.and(&b);
+        assert_eq!(ones(&a), vec![2, SIZE + 1]);
+    }
+
+    #[test]
+    fn and_with_shorter_clears_the_rest() {
+        let mut a = from_ones(SIZE * 3, &[1, SIZE + 1, SIZE * 2 + 1]);
+        let b = from_ones(SIZE, &[1]);
+        a.and(&b);
+        assert_eq!(ones(&a), vec![1]);
+        assert_eq!(a.len(), SIZE * 3);
+    }
+
+    #[test]
+    fn and_with_longer() {
+        let mut a = from_ones(SIZE, &[1, 2]);
+        let b = from_ones(SIZE * 3, &[2, SIZE * 2 + 1]);
+        a.and(&b);
+        assert_eq!(ones(&a), vec![2]);
+        assert_eq!(a.len(), SIZE * 3);
+    }
+
+    #[test]
+    fn or() {
+        let mut a = from_ones(SIZE, &[1, 2]);
+        let b = from_ones(SIZE * 3, &[2, 3, SIZE * 2 + 1]);
+        a.or(&b);
+        assert_eq!(ones(&a), vec![1, 2, 3, SIZE * 2 + 1]);
+        assert_eq!(a.len(), SIZE * 3);
+    }
+
+    #[test]
+    fn or_with_shorter() {
+        let mut a = from_ones(SIZE * 2, &[SIZE + 1]);
+        let b = from_ones(4, &[3]);
+        a.or(&b);
+        assert_eq!(ones(&a), vec![3, SIZE + 1]);
+        assert_eq!(a.len(), SIZE * 2);
+    }
+
+    #[test]
+    fn xor() {
+        let mut a = from_ones(SIZE, &[1, 2]);
+        let b = from_ones(SIZE * 2, &[2, 3, SIZE + 4]);
+        a.xor(&b);
+        assert_eq!(ones(&a), vec![1, 3, SIZE + 4]);
+    }
+
+    #[test]
+    fn xor_with_self_is_empty() {
+        let mut a = from_ones(SIZE * 2, &[1, SIZE + 1]);
+        let b = a.clone();
+        a.xor(&b);
+        assert_eq!(a.count_ones(), 0);
+    }
+
+    #[test]
+    fn not_respects_length() {
+        let mut field = from_ones(SIZE + 3, &[1, SIZE + 1]);
+        field.not();
+
+        assert_eq!(field.count_ones(), SIZE + 3 - 2);
+        assert!(!field.get(1));
+        assert!(!field.get(SIZE + 1));
+        assert!(field.get(SIZE + 2));
+        assert!(!field.get(SIZE + 3));
+        assert_eq!(ones(&field).last(), Some(&(SIZE + 2)));
+    }
+
+    #[test]
+    fn not_twice_is_identity() {
+        let original = from_ones(SIZE * 2 + 9, &[0, 7, SIZE + 3, SIZE * 2 + 8]);
+        let mut field = original.clone();
+        field.not();
+        field.not();
+        assert_eq!(field, original);
+    }
+
+    #[test]
+    fn not_on_word_aligned_length() {
+        let mut field = BitField::init(SIZE);
+        field.not();
+        assert_eq!(field.count_ones(), SIZE);
+    }
+
+    #[test]
+    fn difference_with_and_xor() {
+        // a AND NOT b, the way tag queries evaluate NOT within a universe
+        let universe = from_ones(SIZE * 2, &[0, 1, 2, SIZE, SIZE + 1]);
+        let mut b = from_ones(SIZE * 2, &[1, SIZE]);
+        b.and(&universe);
+        b.xor(&universe);
+        assert_eq!(ones(&b), vec![0, 2, SIZE + 1]);
+    }
+
+    #[test]
+    fn equality_ignores_length() {
+        let a = from_ones(4, &[1]);
+        let b = from_ones(SIZE * 4, &[1]);
+        assert_eq!(a, b);
+        assert_eq!(b, a);
+        assert_eq!(BitField::default(), BitField::init(SIZE * 2));
+    }
+
+    #[test]
+    fn equality_compares_bits() {
+        let a = from_ones(SIZE * 2, &[1, SIZE + 1]);
+        let b = from_ones(SIZE * 2, &[1]);
+        let c = from_ones(4, &[1]);
+        assert_ne!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(c, a);
+    }
+
+    #[test]
+    fn field_iterator_yields_every_bit() {
+        let field = from_ones(SIZE * 2, &[0, 2, SIZE + 1]);
+        let bits: Vec<bool> = field.iter_fields().flatten().collect();
+
+        assert_eq!(bits.len(), SIZE * 2);
+        for (index, bit) in bits.iter().enumerate() {
+            assert_eq!(*bit, [0, 2, SIZE + 1].contains(&index), "bit {index}");
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashSet;
+
+use dioxus::prelude::*;
+
+use crate::{
+    arena::ArenaId,
+    button::{Button, ButtonSize},
+    store::{Bookmark, Collection, CollectionNode},
+    Action,
+};
+
+/// What is being dragged onto a collection. Dioxus doesn't expose the drag's data transfer, so
+/// it is kept in a signal between the drag start and the drop.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Dragged {
+    Bookmark(ArenaId<Bookmark>),
+    Collection(ArenaId<Collection>),
+}
+
+impl Dragged {
+    /// Moves it into `collection`, or to the top level
+    fn drop_into(self, collection: Option<ArenaId<Collection>>) -> Action {
+        match self {
+            Self::Bookmark(id) => Action::FileBookmark { id, collection },
+            Self::Collection(id) => Action::MoveCollection {
+                id,
+                parent: collection,
+            },
+        }
+    }
+}
+
+#[component]
+pub fn CollectionTree(
+    nodes: Vec<CollectionNode>,
+    selected: Option<ArenaId<Collection>>,
+    dragged: Signal<Option<Dragged>>,
+) -> Element {
+    let coroutine = use_coroutine_handle::<Action>();
+
+    let mut collapsed = use_signal(HashSet::<ArenaId<Collection>>::new);
+    let mut is_creating = use_signal(|| false);
+    let mut new_name = use_signal(|| String::new());
+
+    let mut dragged = dragged;
+    let mut drop_into = move |collection: Option<ArenaId<Collection>>| {
+        if let Some(item) = dragged.write().take() {
+            coroutine.send(item.drop_into(collection));
+        }
+    };
+
+    // New collections go in the selected one
+    let mut create = move || {
+        coroutine.send(Action::CreateCollection {
+            name: new_name(),
+            parent: selected,
+        });
+        new_name.set(String::new());
+        is_creating.set(false);
+    };
+
+    // Children of a collapsed collection follow it with a greater depth
+    let mut hidden_below: Option<usize> = None;
+    let visible: Vec<CollectionNode> = nodes
+        .into_iter()
+        .filter(|node| {
+            if hidden_below.is_some_and(|depth| node.depth > depth) {
+                return false;
+            }
+            hidden_below = collapsed.read().contains(&node.id).then_some(node.depth);
+            true
+        })
+        .collect();
+
+    let item_class = |is_selected: bool| {
+        if is_selected {
+            "flex items-center gap-1 pr-4 py-1 rounded-md cursor-pointer select-none bg-tertiary font-bold"
+        } else {
+            "flex items-center gap-1 pr-4 py-1 rounded-md cursor-pointer select-none hover:bg-tertiary"
+        }
+    };
+
+    rsx! {
+        div {
+            class: "w-56 bg-primary border-r border-gray-200",
+            div {
+                class: "sticky top-0 p-4 flex flex-col gap-1",
+                h2 {
+                    class: "text-xl font-bold px-4 pb-4",
+                    "Collections"
+                }
+                div {
+                    class: "{item_class(selected.is_none())} pl-4",
+                    prevent_default: "ondragover ondrop",
+                    onclick: move |_| coroutine.send(Action::SelectCollection { id: None }),
+                    ondragover: move |_| {},
+                    ondrop: move |_| drop_into(None),
+                    span { "All" }
+                }
+                for (id, node) in visible.into_iter().map(|x| (x.id, x)) {
+                    div {
+                        key: "{id.id()}",
+                        class: item_class(selected == Some(id)),
+                        style: "padding-left: {node.depth + 1}rem",
+                        draggable: true,
+                        prevent_default: "ondragover ondrop",
+                        onclick: move |_| coroutine.send(Action::SelectCollection { id: Some(id) }),
+                        ondragstart: move |_| dragged.set(Some(Dragged::Collection(id))),
+                        ondragend: move |_| dragged.set(None),
+                        ondragover: move |_| {},
+                        ondrop: move |_| drop_into(Some(id)),
+                        span {
+                            class: "w-4 text-gray-500",
+                            onclick: move |ev| {
+                                ev.stop_propagation();
+                                let mut collapsed = collapsed.write();
+                                if !collapsed.remove(&id) {
+                                    collapsed.insert(id);
+                                }
+                            },
+                            if !node.has_children {
+                                ""
+                            } else if collapsed.read().contains(&id) {
+                                "▸"
+                            } else {
+                                "▾"
+                            }
+                        }
+                        span {
+                            class: "flex-1 truncate",
+                            "{node.name}"
+                        }
+                        span {
+                            class: "text-gray-500",
+                            "{node.count}"
+                        }
+                        span {
+                            class: "cursor-pointer select-none text-gray-500",
+                            title: "Delete, what is in it moves up",
+                            onclick: move |ev| {
+                                ev.stop_propagation();
+                                coroutine.send(Action::DeleteCollection { id });
+                            },
+                            "×"
+                        }
+                    }
+                }
+                if is_creating() {
+                    input {
+                        class: "bg-primary px-4 h-8 rounded-md border border-gray-300",
+                        placeholder: "Collection name",
+                        value: new_name,
+                        oninput: move |ev| new_name.set(ev.value()),
+                        onkeydown: move |ev| {
+                            if ev.key() == Key::Enter {
+                                create();
+                            }
+                        },
+                    }
+                    Button {
+                        value: "Create",
+                        size: ButtonSize::Small,
+                        onclick: move |_| create(),
+                    }
+                } else {
+                    Button {
+                        value: "New collection",
+                        size: ButtonSize::Small,
+                        onclick: move |_| is_creating.set(true),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use dioxus::prelude::*;
+
+use crate::{
+    button::{Button, ButtonSize},
+    local_file::FileSnapshot,
+    merge::Conflict,
+    store::Bookmark,
+    Action,
+};
+
+/// Bookmarks changed both in the library and in the synced file, waiting for a side to be picked
+pub struct PendingMerge {
+    pub conflicts: Vec<Conflict>,
+    /// Whether the file's version is kept, for each conflict
+    pub keep_theirs: Vec<bool>,
+    /// The file the conflicts were found in, it becomes the base once they are resolved
+    pub snapshot: FileSnapshot,
+}
+
+#[component]
+pub fn ConflictDialog(pending: Signal<Option<PendingMerge>>) -> Element {
+    let coroutine = use_coroutine_handle::<Action>();
+
+    let mut pending = pending;
+    let Some(conflicts) = pending.read().as_ref().map(|x| {
+        x.conflicts
+            .iter()
+            .cloned()
+            .zip(x.keep_theirs.iter().copied())
+            .collect::<Vec<_>>()
+    }) else {
+        return None;
+    };
+
+    let count = conflicts.len();
+    let mut keep_all = move |keep_theirs: bool| {
+        if let Some(pending) = pending.write().as_mut() {
+            pending.keep_theirs.fill(keep_theirs);
+        }
+    };
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black/25 flex items-center justify-center",
+            div {
+                class: "bg-primary w-[64rem] max-h-[80vh] p-8 flex flex-col gap-6 shadow-md rounded-xl",
+                h2 {
+                    class: "text-3xl font-bold",
+                    "The synced file was changed elsewhere"
+                }
+                span {
+                    class: "text-gray-500",
+                    "{count} bookmarks were changed both here and in the file, pick which one to keep."
+                }
+                div {
+                    class: "flex-1 overflow-y-auto flex flex-col gap-4",
+                    for (index, (conflict, keep_theirs)) in conflicts.into_iter().enumerate() {
+                        div {
+                            key: "{index}",
+                            class: "grid grid-cols-2 gap-4",
+                            ConflictSide {
+                                label: "Here",
+                                bookmark: conflict.ours,
+                                selected: !keep_theirs,
+                                onselect: move |_| {
+                                    if let Some(pending) = pending.write().as_mut() {
+                                        pending.keep_theirs[index] = false;
+                                    }
+                                },
+                            }
+                            ConflictSide {
+                                label: "In the file",
+                                bookmark: conflict.theirs,
+                                selected: keep_theirs,
+                                onselect: move |_| {
+                                    if let Some(pending) = pending.write().as_mut() {
+                                        pending.keep_theirs[index] = true;
+                                    }
+                                },
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "flex gap-4 justify-end",
+                    Button {
+                        value: "Keep all here",
+                        size: ButtonSize::Big,
+                        onclick: move |_| keep_all(false),
+                    }
+                    Button {
+                        value: "Keep all in the file",
+                        size: ButtonSize::Big,
+                        onclick: move |_| keep_all(true),
+                    }
+                    Button {
+                        value: "Cancel",
+                        size: ButtonSize::Big,
+                        onclick: move |_| pending.set(None),
+                    }
+                    Button {
+                        value: "Resolve",
+                        size: ButtonSize::Big,
+                        onclick: move |_| coroutine.send(Action::ResolveConflicts),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ConflictSide(
+    label: &'static str,
+    bookmark: Option<Bookmark>,
+    selected: bool,
+    onselect: EventHandler<MouseEvent>,
+) -> Element {
+    let border = if selected {
+        "border-sky-500"
+    } else {
+        "border-gray-200"
+    };
+
+    rsx! {
+        div {
+            class: "flex flex-col px-4 py-2 border-2 rounded-md cursor-pointer break-words {border}",
+            onclick: move |ev| onselect.call(ev),
+            span {
+                class: "text-xs text-gray-500",
+                "{label}"
+            }
+            if let Some(bookmark) = bookmark {
+                span {
+                    class: "text-sm font-bold",
+                    {bookmark.title.to_string()}
+                }
+                span {
+                    class: "text-sky-500 text-sm",
+                    {bookmark.link.to_string()}
+                }
+                if !bookmark.note.is_empty() {
+                    span {
+                        class: "text-sm",
+                        {bookmark.note.to_string()}
+                    }
+                }
+                div {
+                    class: "flex flex-wrap gap-2",
+                    for tag in bookmark.tags.iter() {
+                        span {
+                            key: "{tag}",
+                            class: "px-2 rounded-md bg-tertiary text-xs",
+                            "{tag}"
+                        }
+                    }
+                }
+            } else {
+                span {
+                    class: "text-sm text-gray-500",
+                    "Deleted"
+                }
+            }
+        }
+    }
+}
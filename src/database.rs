@@ -0,0 +1,366 @@
+//! IndexedDB schema. Every version has a [`Migration`], they run in order on upgrade, so a
+//! database created by any older version ends up with the latest schema.
+//!
+//! Schema changes have to happen inside the upgrade transaction, but records can't be rewritten
+//! there asynchronously. So each migration may also rewrite bookmark records after the database
+//! is opened. Rewrites that completed are tracked by [`DATA_VERSION_KEY`], so an interrupted
+//! rewrite is picked up on the next open.
+//!
+//! Bookmarks are keyed by their `id`, a rewritten record whose `id` is not its key is moved.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use idb::{
+    Database, DatabaseEvent, Event, Factory, IndexParams, KeyPath, ObjectStoreParams, Request,
+    Transaction, TransactionMode, VersionChangeEvent,
+};
+use serde::Serialize;
+use serde_wasm_bindgen::Serializer;
+use wasm_bindgen::JsValue;
+
+use crate::store::BookmarkId;
+
+pub use errors::*;
+
+/// Version of the last migration
+pub const DATABASE_VERSION: u32 = 7;
+/// Key in `settings` store, version up to which bookmark rewrites have completed
+pub const DATA_VERSION_KEY: &str = "data_version";
+
+mod errors {
+    #[derive(Debug)]
+    pub enum DatabaseError {
+        Idb(idb::Error),
+        Serde(serde_wasm_bindgen::Error),
+        /// Upgrade transaction is not available during upgrade
+        NoUpgradeTransaction,
+    }
+
+    impl std::error::Error for DatabaseError {}
+    impl std::fmt::Display for DatabaseError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::Idb(err) => write!(f, "IndexedDB error: {}", err),
+                Self::Serde(err) => write!(f, "Serialization error: {}", err),
+                Self::NoUpgradeTransaction => write!(f, "No upgrade transaction"),
+            }
+        }
+    }
+    impl From<idb::Error> for DatabaseError {
+        fn from(err: idb::Error) -> Self {
+            Self::Idb(err)
+        }
+    }
+    impl From<serde_wasm_bindgen::Error> for DatabaseError {
+        fn from(err: serde_wasm_bindgen::Error) -> Self {
+            Self::Serde(err)
+        }
+    }
+}
+
+struct Migration {
+    version: u32,
+    /// Runs inside the upgrade transaction, only for schema changes
+    upgrade: fn(&Database, &Transaction) -> Result<(), idb::Error>,
+    /// Runs for every record of `bookmarks` store after the database is open
+    rewrite_bookmark: Option<fn(&mut serde_json::Value)>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        upgrade: v1,
+        rewrite_bookmark: None,
+    },
+    Migration {
+        version: 2,
+        upgrade: v2,
+        rewrite_bookmark: None,
+    },
+    Migration {
+        version: 3,
+        upgrade: v3,
+        rewrite_bookmark: Some(v3_rewrite_bookmark),
+    },
+    Migration {
+        version: 4,
+        upgrade: v4,
+        rewrite_bookmark: Some(v4_rewrite_bookmark),
+    },
+    Migration {
+        version: 5,
+        upgrade: v5,
+        rewrite_bookmark: Some(v5_rewrite_bookmark),
+    },
+    Migration {
+        version: 6,
+        upgrade: v6,
+        rewrite_bookmark: None,
+    },
+    Migration {
+        version: 7,
+        upgrade: v7,
+        rewrite_bookmark: None,
+    },
+];
+
+/// `bookmarks` and `handles`
+fn v1(database: &Database, _: &Transaction) -> Result<(), idb::Error> {
+    database.create_object_store("bookmarks", ObjectStoreParams::new())?;
+    database.create_object_store("handles", ObjectStoreParams::new())?;
+    Ok(())
+}
+
+/// `settings`
+fn v2(database: &Database, _: &Transaction) -> Result<(), idb::Error> {
+    database.create_object_store("settings", ObjectStoreParams::new())?;
+    Ok(())
+}
+
+/// Indexes on bookmarks
+fn v3(_: &Database, transaction: &Transaction) -> Result<(), idb::Error> {
+    let bookmarks = transaction.object_store("bookmarks")?;
+
+    bookmarks.create_index("link", KeyPath::new_single("link"), None)?;
+    bookmarks.create_index("created_at", KeyPath::new_single("created_at"), None)?;
+
+    let mut tags_params = IndexParams::new();
+    tags_params.multi_entry(true);
+    bookmarks.create_index("tags", KeyPath::new_single("tags"), Some(tags_params))?;
+
+    Ok(())
+}
+
+/// Bookmarks created before tags existed have no `tags`, they wouldn't show up in the index
+fn v3_rewrite_bookmark(bookmark: &mut serde_json::Value) {
+    if let Some(bookmark) = bookmark.as_object_mut() {
+        bookmark
+            .entry("tags")
+            .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+    }
+}
+
+/// Nothing, bookmark ids are added by [`v4_rewrite_bookmark`]
+fn v4(_: &Database, _: &Transaction) -> Result<(), idb::Error> {
+    Ok(())
+}
+
+/// Bookmarks were keyed by their arena slot, which changes on reload
+fn v4_rewrite_bookmark(bookmark: &mut serde_json::Value) {
+    if let Some(bookmark) = bookmark.as_object_mut() {
+        bookmark
+            .entry("id")
+            .or_insert_with(|| serde_json::Value::String(BookmarkId::new().to_string()));
+    }
+}
+
+/// Nothing, timestamps are added by [`v5_rewrite_bookmark`]
+fn v5(_: &Database, _: &Transaction) -> Result<(), idb::Error> {
+    Ok(())
+}
+
+/// Bookmarks without `created_at` wouldn't show up in its index, creation time is unknown so it's 0
+fn v5_rewrite_bookmark(bookmark: &mut serde_json::Value) {
+    if let Some(bookmark) = bookmark.as_object_mut() {
+        for key in ["created_at", "updated_at"] {
+            bookmark
+                .entry(key)
+                .or_insert_with(|| serde_json::Value::from(0));
+        }
+    }
+}
+
+/// `collections`, bookmarks without `collection` are not filed in one
+fn v6(database: &Database, _: &Transaction) -> Result<(), idb::Error> {
+    database.create_object_store("collections", ObjectStoreParams::new())?;
+    Ok(())
+}
+
+/// `history`, auto incremented and indexed by bookmark id
+fn v7(database: &Database, _: &Transaction) -> Result<(), idb::Error> {
+    let mut params = ObjectStoreParams::new();
+    params.auto_increment(true);
+    let history = database.create_object_store("history", params)?;
+    history.create_index("bookmark", KeyPath::new_single("bookmark"), None)?;
+    Ok(())
+}
+
+/// Opens the database and runs the migrations it is missing.
+///
+/// Every library has its own database, see [`crate::library::LibraryId::storage_name`].
+pub async fn open(name: &str) -> Result<Database, DatabaseError> {
+    let factory = Factory::new()?;
+    let mut request = factory.open(name, Some(DATABASE_VERSION))?;
+
+    // Version before the upgrade, None if there was no upgrade
+    let upgraded_from = Rc::new(Cell::new(None));
+    // Why the upgrade failed, the request itself only fails with an abort error
+    let upgrade_error = Rc::new(RefCell::new(None));
+
+    request.on_upgrade_needed({
+        let upgraded_from = upgraded_from.clone();
+        let upgrade_error = upgrade_error.clone();
+        move |ev| {
+            let result = ev
+                .old_version()
+                .map_err(DatabaseError::from)
+                .and_then(|old_version| {
+                    upgraded_from.set(Some(old_version));
+                    upgrade(&ev, old_version)
+                });
+
+            if let Err(err) = result {
+                // Nothing of a failed upgrade is kept, the database stays at its old version
+                if let Some(transaction) = ev.target().ok().and_then(|x| x.transaction()) {
+                    let _ = transaction.abort();
+                }
+                upgrade_error.replace(Some(err));
+            }
+        }
+    });
+
+    let database = request.await;
+    if let Some(err) = upgrade_error.take() {
+        return Err(err);
+    }
+    let database = database?;
+
+    rewrite_bookmarks(&database, upgraded_from.get()).await?;
+
+    Ok(database)
+}
+
+fn upgrade(ev: &VersionChangeEvent, old_version: u32) -> Result<(), DatabaseError> {
+    let database = ev.database()?;
+    let transaction = ev
+        .target()?
+        .transaction()
+        .ok_or(DatabaseError::NoUpgradeTransaction)?;
+
+    for migration in MIGRATIONS.iter().filter(|x| x.version > old_version) {
+        (migration.upgrade)(&database, &transaction)?;
+    }
+
+    Ok(())
+}
+
+async fn rewrite_bookmarks(
+    database: &Database,
+    upgraded_from: Option<u32>,
+) -> Result<(), DatabaseError> {
+    let serializer = Serializer::json_compatible();
+    let data_version_key = JsValue::from_str(DATA_VERSION_KEY);
+
+    let transaction =
+        database.transaction(&["bookmarks", "settings"], TransactionMode::ReadWrite)?;
+    let bookmarks = transaction.object_store("bookmarks")?;
+    let settings = transaction.object_store("settings")?;
+
+    let data_version = settings
+        .get(data_version_key.clone())?
+        .await?
+        .and_then(|x| x.as_f64())
+        .map(|x| x as u32);
+
+    // Databases from before data version was tracked did all the rewrites up to their version
+    let data_version = data_version.or(upgraded_from).unwrap_or(DATABASE_VERSION);
+
+    let rewrites: Vec<_> = MIGRATIONS
+        .iter()
+        .filter(|x| x.version > data_version)
+        .filter_map(|x| x.rewrite_bookmark)
+        .collect();
+
+    if !rewrites.is_empty() {
+        let keys = bookmarks.get_all_keys(None, None)?.await?;
+        let values = bookmarks.get_all(None, None)?.await?;
+
+        for (key, value) in keys.into_iter().zip(values) {
+            let mut bookmark: serde_json::Value = serde_wasm_bindgen::from_value(value)?;
+            for rewrite in rewrites.iter() {
+                rewrite(&mut bookmark);
+            }
+            let value = bookmark.serialize(&serializer)?;
+
+            match bookmark["id"].as_str().map(JsValue::from_str) {
+                Some(id) if id != key => {
+                    bookmarks.delete(key)?.await?;
+                    bookmarks.put(&value, Some(&id))?.await?;
+                }
+                _ => {
+                    bookmarks.put(&value, Some(&key))?.await?;
+                }
+            }
+        }
+    }
+
+    if data_version != DATABASE_VERSION {
+        settings
+            .put(
+                &JsValue::from_f64(DATABASE_VERSION as f64),
+                Some(&data_version_key),
+            )?
+            .await?;
+    }
+
+    transaction.await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        v3_rewrite_bookmark, v4_rewrite_bookmark, v5_rewrite_bookmark, DATABASE_VERSION, MIGRATIONS,
+    };
+
+    #[test]
+    fn migrations_are_in_order() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as u32 + 1);
+        }
+        assert_eq!(MIGRATIONS.last().map(|x| x.version), Some(DATABASE_VERSION));
+    }
+
+    #[test]
+    fn v3_adds_missing_tags() {
+        let mut bookmark = serde_json::json!({ "title": "a", "link": "b", "note": "" });
+        v3_rewrite_bookmark(&mut bookmark);
+        assert_eq!(bookmark["tags"], serde_json::json!([]));
+
+        let mut bookmark =
+            serde_json::json!({ "title": "a", "link": "b", "note": "", "tags": ["x"] });
+        v3_rewrite_bookmark(&mut bookmark);
+        assert_eq!(bookmark["tags"], serde_json::json!(["x"]));
+    }
+
+    #[test]
+    fn v4_adds_missing_id() {
+        let mut bookmark = serde_json::json!({ "title": "a", "link": "b", "note": "", "tags": [] });
+        v4_rewrite_bookmark(&mut bookmark);
+        let id = bookmark["id"]
+            .as_str()
+            .expect("id should be added")
+            .to_owned();
+        assert!(serde_json::from_value::<crate::store::Bookmark>(bookmark.clone()).is_ok());
+
+        v4_rewrite_bookmark(&mut bookmark);
+        assert_eq!(bookmark["id"].as_str(), Some(id.as_str()));
+    }
+
+    #[test]
+    fn v5_adds_missing_timestamps() {
+        let mut bookmark = serde_json::json!({ "title": "a", "link": "b", "note": "" });
+        v5_rewrite_bookmark(&mut bookmark);
+        assert_eq!(bookmark["created_at"], serde_json::json!(0));
+        assert_eq!(bookmark["updated_at"], serde_json::json!(0));
+
+        let mut bookmark = serde_json::json!({ "created_at": 1, "updated_at": 2 });
+        v5_rewrite_bookmark(&mut bookmark);
+        assert_eq!(bookmark["created_at"], serde_json::json!(1));
+        assert_eq!(bookmark["updated_at"], serde_json::json!(2));
+    }
+}
@@ -0,0 +1,96 @@
+//! Word diffs of bookmark fields, shown in the history panel
+
+/// Part of the new text compared to the old one
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Diff {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Words and the whitespace between them, so joining them gives back the text
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut is_space = None;
+    for (index, char) in text.char_indices() {
+        let char_is_space = char.is_whitespace();
+        if is_space.is_some_and(|x| x != char_is_space) {
+            tokens.push(&text[start..index]);
+            start = index;
+        }
+        is_space = Some(char_is_space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// Longest common subsequence of words, removals come before additions where both changed
+pub fn diff(old: &str, new: &str) -> Vec<Diff> {
+    let (old, new) = (tokenize(old), tokenize(new));
+
+    // lengths[i][j] is the common length of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut parts: Vec<Diff> = Vec::new();
+    let mut push = |part: Diff| match (parts.last_mut(), part) {
+        (Some(Diff::Same(last)), Diff::Same(text))
+        | (Some(Diff::Removed(last)), Diff::Removed(text))
+        | (Some(Diff::Added(last)), Diff::Added(text)) => last.push_str(&text),
+        (_, part) => parts.push(part),
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            push(Diff::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            push(Diff::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            push(Diff::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, Diff};
+
+    #[test]
+    fn diffs_words() {
+        assert_eq!(
+            diff("The Rust book", "The Rust programming book"),
+            vec![
+                Diff::Same("The Rust ".to_string()),
+                Diff::Added("programming ".to_string()),
+                Diff::Same("book".to_string()),
+            ]
+        );
+        assert_eq!(
+            diff("https://rust-lang.org/", "https://www.rust-lang.org/"),
+            vec![
+                Diff::Removed("https://rust-lang.org/".to_string()),
+                Diff::Added("https://www.rust-lang.org/".to_string()),
+            ]
+        );
+        assert_eq!(diff("a b", ""), vec![Diff::Removed("a b".to_string())]);
+        assert_eq!(diff("", ""), vec![]);
+    }
+}
@@ -0,0 +1,126 @@
+use dioxus::prelude::*;
+
+use crate::{
+    arena::ArenaId,
+    button::{Button, ButtonSize},
+    store::{Bookmark, Store},
+    Action,
+};
+
+/// Bookmarks of each link saved more than once, see [`Store::duplicates`]
+pub type DuplicateGroups = Vec<Vec<(ArenaId<Bookmark>, Bookmark)>>;
+
+pub fn duplicate_groups(store: &Store) -> DuplicateGroups {
+    store
+        .duplicates()
+        .into_iter()
+        .map(|ids| {
+            ids.into_iter()
+                .map(|id| (id, store.bookmark(id).clone()))
+                .collect()
+        })
+        .collect()
+}
+
+/// Merging keeps the oldest bookmark of a group, see [`Store::merge_duplicates`]
+#[component]
+pub fn DuplicatesDialog(groups: Signal<Option<DuplicateGroups>>) -> Element {
+    let coroutine = use_coroutine_handle::<Action>();
+
+    let mut groups = groups;
+    let Some(current) = groups.read().clone() else {
+        return None;
+    };
+
+    let ids = |group: &[(ArenaId<Bookmark>, Bookmark)]| -> Vec<ArenaId<Bookmark>> {
+        group.iter().map(|(id, _)| *id).collect()
+    };
+    let all: Vec<Vec<ArenaId<Bookmark>>> = current.iter().map(|x| ids(x)).collect();
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black/25 flex items-center justify-center",
+            div {
+                class: "bg-primary w-[48rem] max-h-[80vh] p-8 flex flex-col gap-6 shadow-md rounded-xl",
+                h2 {
+                    class: "text-3xl font-bold",
+                    "{current.len()} links saved more than once"
+                }
+                div {
+                    class: "flex-1 overflow-y-auto flex flex-col gap-2",
+                    if current.is_empty() {
+                        span {
+                            class: "text-gray-500",
+                            "No duplicates"
+                        }
+                    }
+                    for group in current.iter() {
+                        div {
+                            key: "{group[0].1.id}",
+                            class: "flex flex-col gap-1 px-4 py-2 border-b border-gray-200 break-words",
+                            div {
+                                class: "flex items-center gap-2",
+                                span {
+                                    class: "flex-1 text-sky-500 text-sm",
+                                    {group[0].1.link.to_string()}
+                                }
+                                Button {
+                                    value: "Merge",
+                                    size: ButtonSize::Small,
+                                    onclick: {
+                                        let group = ids(group);
+                                        move |_| {
+                                            coroutine.send(Action::MergeDuplicates {
+                                                groups: vec![group.clone()],
+                                            })
+                                        }
+                                    },
+                                }
+                            }
+                            for (_, bookmark) in group.iter() {
+                                div {
+                                    key: "{bookmark.id}",
+                                    class: "flex flex-col pl-4",
+                                    span {
+                                        class: "text-sm font-bold",
+                                        {bookmark.title.to_string()}
+                                    }
+                                    if !bookmark.note.is_empty() {
+                                        span {
+                                            class: "text-sm text-gray-500",
+                                            {bookmark.note.to_string()}
+                                        }
+                                    }
+                                    div {
+                                        class: "flex flex-wrap gap-2",
+                                        for tag in bookmark.tags.iter() {
+                                            span {
+                                                key: "{tag}",
+                                                class: "px-2 rounded-md bg-tertiary text-xs",
+                                                "{tag}"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "flex gap-4 justify-end",
+                    Button {
+                        value: "Close",
+                        size: ButtonSize::Big,
+                        onclick: move |_| groups.set(None),
+                    }
+                    Button {
+                        value: "Merge all",
+                        size: ButtonSize::Big,
+                        disabled: all.is_empty(),
+                        onclick: move |_| coroutine.send(Action::MergeDuplicates { groups: all.clone() }),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{
+    file_system::{FileSystemError, FileSystemJsError},
+    import::ImportError,
+    local_file::LocalFileError,
+    storage::StorageError,
+};
+
+/// Everything that can fail while handling an action
+#[derive(Debug)]
+pub enum AppError {
+    Storage(StorageError),
+    FileSystem(FileSystemError),
+    Json(serde_json::Error),
+    Import(ImportError),
+    LocalFile(LocalFileError),
+}
+
+impl AppError {
+    /// User dismissed a file picker, nothing to report
+    pub fn is_cancelled(&self) -> bool {
+        use FileSystemJsError as FSJE;
+
+        matches!(
+            self,
+            Self::FileSystem(FileSystemError::JsError(
+                FSJE::ShowSaveFilePickerAbort | FSJE::ShowOpenFilePickerAbort
+            ))
+        )
+    }
+}
+
+impl std::error::Error for AppError {}
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Storage(err) => write!(f, "{}", err),
+            Self::FileSystem(err) => write!(f, "File system error: {}", err),
+            Self::Json(err) => write!(f, "JSON error: {}", err),
+            Self::Import(err) => write!(f, "{}", err),
+            Self::LocalFile(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<StorageError> for AppError {
+    fn from(err: StorageError) -> Self {
+        Self::Storage(err)
+    }
+}
+impl From<FileSystemError> for AppError {
+    fn from(err: FileSystemError) -> Self {
+        Self::FileSystem(err)
+    }
+}
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+impl From<ImportError> for AppError {
+    fn from(err: ImportError) -> Self {
+        Self::Import(err)
+    }
+}
+impl From<LocalFileError> for AppError {
+    fn from(err: LocalFileError) -> Self {
+        Self::LocalFile(err)
+    }
+}
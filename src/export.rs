@@ -0,0 +1,278 @@
+//! Formats the library can be exported to. Each one has an [`Exporter`], they only turn
+//! bookmarks into text so they work the same in tests as in the browser.
+//!
+//! Formats with sections or folders write the collection tree, see [`folders`]. Bookmarks in no
+//! collection are grouped by their first tag only, so every bookmark is written once and importing
+//! the file back doesn't duplicate it. All of its tags are still written next to it.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    local_file::{ToLocalFile, LOCAL_FILE_VERSION},
+    netscape,
+    store::{Bookmark, Collection, CollectionId},
+};
+
+mod csv;
+mod markdown;
+mod opml;
+
+pub trait Exporter {
+    /// Shown in the export menu
+    fn name(&self) -> &'static str;
+    /// Suggested when asking where to save
+    fn file_name(&self) -> &'static str;
+    /// Bookmarks are written in their collection, see [`folders`]
+    fn export(
+        &self,
+        bookmarks: &[&Bookmark],
+        collections: &[&Collection],
+    ) -> Result<String, serde_json::Error>;
+}
+
+/// In the order they are listed in the export menu
+pub const EXPORTERS: &[&dyn Exporter] = &[
+    &LocalFileExporter,
+    &NetscapeExporter,
+    &markdown::Markdown,
+    &csv::Csv,
+    &opml::Opml,
+];
+
+/// Librarian's own file, the one sync writes, see [`crate::local_file`]
+pub struct LocalFileExporter;
+
+impl Exporter for LocalFileExporter {
+    fn name(&self) -> &'static str {
+        "Librarian JSON"
+    }
+    fn file_name(&self) -> &'static str {
+        "librarian.json"
+    }
+    fn export(
+        &self,
+        bookmarks: &[&Bookmark],
+        collections: &[&Collection],
+    ) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&ToLocalFile {
+            version: LOCAL_FILE_VERSION,
+            bookmarks: bookmarks.to_vec(),
+            collections: collections.to_vec(),
+        })
+    }
+}
+
+/// `bookmarks.html` browsers can import, see [`crate::netscape`]
+struct NetscapeExporter;
+
+impl Exporter for NetscapeExporter {
+    fn name(&self) -> &'static str {
+        "Browser HTML"
+    }
+    fn file_name(&self) -> &'static str {
+        "bookmarks.html"
+    }
+    fn export(
+        &self,
+        bookmarks: &[&Bookmark],
+        collections: &[&Collection],
+    ) -> Result<String, serde_json::Error> {
+        Ok(netscape::write(&folders(bookmarks, collections)))
+    }
+}
+
+/// A collection and what is in it, or the top level
+pub struct Folder<'a> {
+    /// Empty at the top level
+    pub name: &'a str,
+    pub bookmarks: Vec<&'a Bookmark>,
+    pub folders: Vec<Folder<'a>>,
+}
+
+/// Collections as nested folders sorted by name, followed by a folder for every first tag of
+/// bookmarks in no collection. Bookmarks without either are at the top level. A collection whose
+/// parent is gone is at the top level too, collections in a cycle of parents are left out and
+/// their bookmarks are written as if they were in none.
+pub fn folders<'a>(bookmarks: &[&'a Bookmark], collections: &[&'a Collection]) -> Folder<'a> {
+    let ids: HashSet<CollectionId> = collections.iter().map(|x| x.id).collect();
+    let mut children: HashMap<Option<CollectionId>, Vec<&Collection>> = HashMap::new();
+    for collection in collections.iter().copied() {
+        let parent = collection.parent.filter(|x| ids.contains(x));
+        children.entry(parent).or_default().push(collection);
+    }
+    for collections in children.values_mut() {
+        collections.sort_by_cached_key(|x| x.name.to_lowercase());
+    }
+
+    // Collections in a cycle are never reached from the top level
+    let mut reached: HashSet<CollectionId> = HashSet::new();
+    let mut stack: Vec<Option<CollectionId>> = vec![None];
+    while let Some(parent) = stack.pop() {
+        for collection in children.get(&parent).into_iter().flatten() {
+            if reached.insert(collection.id) {
+                stack.push(Some(collection.id));
+            }
+        }
+    }
+
+    let mut filed: HashMap<CollectionId, Vec<&Bookmark>> = HashMap::new();
+    let mut unfiled = Vec::new();
+    let mut tagged: BTreeMap<&str, Vec<&Bookmark>> = BTreeMap::new();
+    for bookmark in bookmarks.iter().copied() {
+        match (
+            bookmark.collection.filter(|x| reached.contains(x)),
+            bookmark.tags.first(),
+        ) {
+            (Some(collection), _) => filed.entry(collection).or_default().push(bookmark),
+            (None, Some(tag)) => tagged.entry(tag).or_default().push(bookmark),
+            (None, None) => unfiled.push(bookmark),
+        }
+    }
+
+    fn folder<'a>(
+        collection: &'a Collection,
+        children: &mut HashMap<Option<CollectionId>, Vec<&'a Collection>>,
+        filed: &mut HashMap<CollectionId, Vec<&'a Bookmark>>,
+    ) -> Folder<'a> {
+        Folder {
+            name: &collection.name,
+            bookmarks: filed.remove(&collection.id).unwrap_or_default(),
+            folders: children
+                .remove(&Some(collection.id))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|x| folder(x, children, filed))
+                .collect(),
+        }
+    }
+
+    let mut folders: Vec<Folder> = children
+        .remove(&None)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|x| folder(x, &mut children, &mut filed))
+        .collect();
+    folders.extend(tagged.into_iter().map(|(tag, bookmarks)| Folder {
+        name: tag,
+        bookmarks,
+        folders: Vec::new(),
+    }));
+
+    Folder {
+        name: "",
+        bookmarks: unfiled,
+        folders,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{folders, Folder, EXPORTERS};
+    use crate::{
+        import,
+        settings::Settings,
+        store::{Bookmark, Collection, CollectionId},
+    };
+
+    /// A line per folder and bookmark, indented by depth
+    fn outline(folder: &Folder, indent: &str, lines: &mut Vec<String>) {
+        for child in folder.folders.iter() {
+            lines.push(format!("{indent}{}/", child.name));
+            outline(child, &format!("{indent}  "), lines);
+        }
+        for bookmark in folder.bookmarks.iter() {
+            lines.push(format!("{indent}{}", bookmark.title));
+        }
+    }
+
+    #[test]
+    fn names_are_unique() {
+        for (index, exporter) in EXPORTERS.iter().enumerate() {
+            assert!(EXPORTERS[..index]
+                .iter()
+                .all(|x| x.name() != exporter.name() && x.file_name() != exporter.file_name()));
+        }
+    }
+
+    #[test]
+    fn writes_collections_as_folders() {
+        let languages = Collection::new("Languages", None, 0);
+        let web = Collection::new("Web", Some(languages.id), 0);
+        let archive = Collection::new("archive", None, 0);
+        let mut a = Collection::new("A", None, 0);
+        let b = Collection::new("B", Some(a.id), 0);
+        a.parent = Some(b.id);
+
+        let in_collection = |title: &str, collection: &Collection| Bookmark {
+            collection: Some(collection.id),
+            ..Bookmark::new(title, "https://example.com", "", 0)
+        };
+        let bookmarks = [
+            Bookmark::new("Example", "https://example.com", "", 0),
+            in_collection("Rust", &languages).with_tags(&["lang"]),
+            in_collection("Dioxus", &web),
+            Bookmark::new("Tokio", "https://tokio.rs", "", 0).with_tags(&["async", "rust"]),
+            // Collections in a cycle and missing ones are not written
+            in_collection("Lost", &a),
+            Bookmark {
+                collection: Some(CollectionId::new()),
+                ..Bookmark::new("Gone", "https://example.com", "", 0).with_tags(&["old"])
+            },
+        ];
+
+        let root = folders(
+            &bookmarks.iter().collect::<Vec<_>>(),
+            &[&web, &a, &languages, &b, &archive],
+        );
+        let mut lines = Vec::new();
+        outline(&root, "", &mut lines);
+
+        assert_eq!(
+            lines,
+            [
+                "archive/",
+                "Languages/",
+                "  Web/",
+                "    Dioxus",
+                "  Rust",
+                "async/",
+                "  Tokio",
+                "old/",
+                "  Gone",
+                "Example",
+                "Lost",
+            ]
+        );
+    }
+
+    #[test]
+    fn importable_exports_round_trip() {
+        let bookmarks = [
+            Bookmark::new(
+                "Rust",
+                "https://rust-lang.org/",
+                "A language",
+                1717243200000,
+            )
+            .with_tags(&["lang"]),
+            Bookmark::new("Untagged", "https://example.com/", "", 1717243200000),
+        ];
+        let bookmarks: Vec<&Bookmark> = bookmarks.iter().collect();
+
+        for name in ["Librarian JSON", "Browser HTML"] {
+            let exporter = EXPORTERS.iter().find(|x| x.name() == name).unwrap();
+            let file_data = exporter.export(&bookmarks, &[]).unwrap();
+
+            let imported = import::detect(&file_data)
+                .unwrap()
+                .import(&file_data, &Settings::default().allowed_schemes)
+                .unwrap();
+            let links: Vec<&str> = imported.bookmarks.iter().map(|x| &*x.link).collect();
+            assert_eq!(
+                links,
+                ["https://rust-lang.org/", "https://example.com/"],
+                "{name}"
+            );
+        }
+    }
+}
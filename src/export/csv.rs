@@ -0,0 +1,139 @@
+//! One row per bookmark with every field, for spreadsheets. Timestamps are ISO 8601 and empty
+//! when unknown, tags are comma separated and the collection is a path like `Languages/Rust`.
+
+use std::collections::HashMap;
+
+use super::Exporter;
+use crate::{
+    store::{Bookmark, Collection, CollectionId},
+    time::{self, Timestamp},
+};
+
+pub struct Csv;
+
+const HEADER: [&str; 9] = [
+    "id",
+    "title",
+    "url",
+    "note",
+    "tags",
+    "collection",
+    "created",
+    "updated",
+    "visited",
+];
+
+impl Exporter for Csv {
+    fn name(&self) -> &'static str {
+        "CSV"
+    }
+    fn file_name(&self) -> &'static str {
+        "bookmarks.csv"
+    }
+    fn export(
+        &self,
+        bookmarks: &[&Bookmark],
+        collections: &[&Collection],
+    ) -> Result<String, serde_json::Error> {
+        let paths = paths(collections);
+        let date = |timestamp: Timestamp| match timestamp {
+            0 => String::new(),
+            timestamp => time::format_iso8601(timestamp),
+        };
+
+        let mut file_data = HEADER.join(",");
+        file_data.push_str("\r\n");
+
+        for bookmark in bookmarks {
+            let row = [
+                bookmark.id.to_string(),
+                bookmark.title.to_string(),
+                bookmark.link.to_string(),
+                bookmark.note.to_string(),
+                bookmark.tags.join(","),
+                bookmark
+                    .collection
+                    .and_then(|x| paths.get(&x))
+                    .cloned()
+                    .unwrap_or_default(),
+                date(bookmark.created_at),
+                date(bookmark.updated_at),
+                date(bookmark.last_visited_at.unwrap_or_default()),
+            ];
+            let row: Vec<String> = row.iter().map(|x| quote(x)).collect();
+
+            file_data.push_str(&row.join(","));
+            file_data.push_str("\r\n");
+        }
+
+        Ok(file_data)
+    }
+}
+
+/// Names from the top level down to each collection, joined with `/`
+fn paths(collections: &[&Collection]) -> HashMap<CollectionId, String> {
+    let by_id: HashMap<CollectionId, &Collection> =
+        collections.iter().map(|x| (x.id, *x)).collect();
+
+    collections
+        .iter()
+        .map(|collection| {
+            // Parents can be in a cycle, so at most as many as there are collections
+            let mut names: Vec<&str> = std::iter::successors(Some(*collection), |x| {
+                x.parent.and_then(|x| by_id.get(&x)).copied()
+            })
+            .take(collections.len())
+            .map(|x| &*x.name)
+            .collect();
+            names.reverse();
+            (collection.id, names.join("/"))
+        })
+        .collect()
+}
+
+/// Quotes fields with separators, quotes or line breaks, doubling the quotes
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Csv;
+    use crate::{
+        export::Exporter,
+        store::{Bookmark, Collection},
+    };
+
+    #[test]
+    fn writes_rows() {
+        let languages = Collection::new("Languages", None, 0);
+        let rust = Collection::new("Rust", Some(languages.id), 0);
+
+        let mut a = Bookmark::new("Say \"hi\"", "https://a.com", "one\ntwo", 1717243200000)
+            .with_tags(&["rust", "web"]);
+        a.last_visited_at = Some(1717243260000);
+        a.collection = Some(rust.id);
+        let mut b = Bookmark::new("B", "https://b.com", "", 1717243200000);
+        b.created_at = 0;
+        b.updated_at = 0;
+
+        let file_data = Csv.export(&[&a, &b], &[&rust, &languages]).unwrap();
+
+        assert_eq!(
+            file_data,
+            format!(
+                concat!(
+                    "id,title,url,note,tags,collection,created,updated,visited\r\n",
+                    "{},\"Say \"\"hi\"\"\",https://a.com,\"one\ntwo\",\"rust,web\",Languages/Rust,",
+                    "2024-06-01T12:00:00.000Z,2024-06-01T12:00:00.000Z,2024-06-01T12:01:00.000Z\r\n",
+                    "{},B,https://b.com,,,,,,\r\n",
+                ),
+                a.id, b.id
+            )
+        );
+    }
+}
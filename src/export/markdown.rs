@@ -0,0 +1,128 @@
+//! A Markdown list with a section per collection and per first tag of bookmarks in none, see
+//! [`folders`]. Sections of nested collections are nested headings, notes are blockquotes. For
+//! reading more than for importing.
+
+use std::fmt::Write;
+
+use super::{folders, Exporter, Folder};
+use crate::store::{Bookmark, Collection};
+
+pub struct Markdown;
+
+impl Exporter for Markdown {
+    fn name(&self) -> &'static str {
+        "Markdown"
+    }
+    fn file_name(&self) -> &'static str {
+        "bookmarks.md"
+    }
+    fn export(
+        &self,
+        bookmarks: &[&Bookmark],
+        collections: &[&Collection],
+    ) -> Result<String, serde_json::Error> {
+        let mut file_data = String::from("# Bookmarks\n");
+        write_folder(&mut file_data, &folders(bookmarks, collections), 1);
+
+        Ok(file_data)
+    }
+}
+
+/// Bookmarks, then a section for every folder one heading level deeper
+fn write_folder(file_data: &mut String, folder: &Folder, level: usize) {
+    if !folder.bookmarks.is_empty() {
+        file_data.push('\n');
+    }
+    for bookmark in folder.bookmarks.iter() {
+        write_bookmark(file_data, bookmark);
+    }
+    for child in folder.folders.iter() {
+        // Markdown has no headings deeper than 6
+        let level = (level + 1).min(6);
+        let _ = writeln!(file_data, "\n{} {}", "#".repeat(level), escape(child.name));
+        write_folder(file_data, child, level);
+    }
+}
+
+fn write_bookmark(file_data: &mut String, bookmark: &Bookmark) {
+    let _ = write!(
+        file_data,
+        "- [{}]({})",
+        escape(&bookmark.title),
+        escape_link(&bookmark.link)
+    );
+    for tag in bookmark.tags.iter() {
+        let _ = write!(file_data, " `{tag}`");
+    }
+    file_data.push('\n');
+
+    for line in bookmark.note.lines() {
+        let _ = writeln!(file_data, "  > {}", escape(line));
+    }
+}
+
+/// Backslashes before what would be read as formatting
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        if matches!(
+            character,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+/// Percent encodes what would end the link early
+fn escape_link(link: &str) -> String {
+    link.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Markdown;
+    use crate::{export::Exporter, store::Bookmark};
+
+    #[test]
+    fn groups_by_tag() {
+        let bookmarks = [
+            Bookmark::new(
+                "The Book",
+                "https://doc.rust-lang.org/book/",
+                "Start here\nthen *Rustonomicon*",
+                1717243200000,
+            )
+            .with_tags(&["rust", "learning"]),
+            Bookmark::new("Example", "https://example.com/a_(b)", "", 1717243200000),
+            Bookmark::new("[Deno]", "https://deno.com", "", 1717243200000).with_tags(&["js"]),
+        ];
+
+        let file_data = Markdown
+            .export(&bookmarks.iter().collect::<Vec<_>>(), &[])
+            .unwrap();
+
+        assert_eq!(
+            file_data,
+            concat!(
+                "# Bookmarks\n",
+                "\n",
+                "- [Example](https://example.com/a_%28b%29)\n",
+                "\n",
+                "## js\n",
+                "\n",
+                "- [\\[Deno\\]](https://deno.com) `js`\n",
+                "\n",
+                "## rust\n",
+                "\n",
+                "- [The Book](https://doc.rust-lang.org/book/) `rust` `learning`\n",
+                "  > Start here\n",
+                "  > then \\*Rustonomicon\\*\n",
+            )
+        );
+    }
+}
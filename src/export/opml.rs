@@ -0,0 +1,115 @@
+//! OPML 2.0 outline with nested folders for collections and a folder per first tag of bookmarks in
+//! none, see [`folders`]. Read by outliners and feed readers.
+
+use std::fmt::Write;
+
+use super::{folders, Exporter, Folder};
+use crate::{
+    netscape::encode,
+    store::{Bookmark, Collection},
+};
+
+pub struct Opml;
+
+impl Exporter for Opml {
+    fn name(&self) -> &'static str {
+        "OPML"
+    }
+    fn file_name(&self) -> &'static str {
+        "bookmarks.opml"
+    }
+    fn export(
+        &self,
+        bookmarks: &[&Bookmark],
+        collections: &[&Collection],
+    ) -> Result<String, serde_json::Error> {
+        let mut file_data = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<opml version=\"2.0\">\n",
+            "  <head>\n",
+            "    <title>Bookmarks</title>\n",
+            "  </head>\n",
+            "  <body>\n",
+        ));
+
+        write_folder(&mut file_data, &folders(bookmarks, collections), "    ");
+
+        file_data.push_str("  </body>\n</opml>\n");
+        Ok(file_data)
+    }
+}
+
+/// Folders first, then bookmarks
+fn write_folder(file_data: &mut String, folder: &Folder, indent: &str) {
+    for child in folder.folders.iter() {
+        let _ = writeln!(
+            file_data,
+            "{indent}<outline text=\"{}\">",
+            encode(child.name)
+        );
+        write_folder(file_data, child, &format!("{indent}  "));
+        let _ = writeln!(file_data, "{indent}</outline>");
+    }
+    for bookmark in folder.bookmarks.iter() {
+        write_bookmark(file_data, bookmark, indent);
+    }
+}
+
+fn write_bookmark(file_data: &mut String, bookmark: &Bookmark, indent: &str) {
+    let _ = write!(
+        file_data,
+        "{indent}<outline type=\"link\" text=\"{}\" url=\"{}\"",
+        encode(&bookmark.title),
+        encode(&bookmark.link)
+    );
+    if !bookmark.tags.is_empty() {
+        let _ = write!(
+            file_data,
+            " category=\"{}\"",
+            encode(&bookmark.tags.join(","))
+        );
+    }
+    if !bookmark.note.is_empty() {
+        // Line breaks in attributes would be read as spaces
+        let note = encode(&bookmark.note).replace('\n', "&#10;");
+        let _ = write!(file_data, " description=\"{note}\"");
+    }
+    file_data.push_str("/>\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Opml;
+    use crate::{export::Exporter, store::Bookmark};
+
+    #[test]
+    fn writes_outline() {
+        let bookmarks = [
+            Bookmark::new("A & B", "https://a.com/?a=1&b=2", "one\ntwo", 1717243200000)
+                .with_tags(&["news"]),
+            Bookmark::new("C", "https://c.com", "", 1717243200000),
+        ];
+
+        let file_data = Opml
+            .export(&bookmarks.iter().collect::<Vec<_>>(), &[])
+            .unwrap();
+
+        assert_eq!(
+            file_data,
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<opml version=\"2.0\">\n",
+                "  <head>\n",
+                "    <title>Bookmarks</title>\n",
+                "  </head>\n",
+                "  <body>\n",
+                "    <outline text=\"news\">\n",
+                "      <outline type=\"link\" text=\"A &amp; B\" url=\"https://a.com/?a=1&amp;b=2\" category=\"news\" description=\"one&#10;two\"/>\n",
+                "    </outline>\n",
+                "    <outline type=\"link\" text=\"C\" url=\"https://c.com\"/>\n",
+                "  </body>\n",
+                "</opml>\n",
+            )
+        );
+    }
+}
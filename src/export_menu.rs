@@ -0,0 +1,41 @@
+use dioxus::prelude::*;
+
+use crate::{
+    button::{Button, ButtonSize},
+    export::EXPORTERS,
+    Action,
+};
+
+#[component]
+pub fn ExportMenu() -> Element {
+    let coroutine = use_coroutine_handle::<Action>();
+
+    let mut is_open = use_signal(|| false);
+
+    rsx! {
+        span {
+            class: "mx-8 relative",
+            Button {
+                value: "Export as…",
+                size: ButtonSize::Big,
+                onclick: move |_| is_open.set(!is_open()),
+            }
+            if is_open() {
+                div {
+                    class: "absolute top-10 left-0 w-48 py-2 flex flex-col bg-primary shadow-md rounded-md border border-gray-200",
+                    for exporter in EXPORTERS.iter().copied() {
+                        span {
+                            key: "{exporter.name()}",
+                            class: "px-4 py-1 cursor-pointer select-none hover:bg-tertiary",
+                            onclick: move |_| {
+                                is_open.set(false);
+                                coroutine.send(Action::Export { exporter });
+                            },
+                            {exporter.name()}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
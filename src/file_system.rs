@@ -1,12 +1,17 @@
 use wasm_bindgen::{JsCast, JsValue};
 use web_sys::FileSystemFileHandle;
 
+use crate::local_file::FileSnapshot;
+
 pub use errors::*;
 
 mod errors {
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
     pub enum FileSystemError {
         NotAFileSystemFileHandle,
+        NotAString,
+        NotABool,
+        NotAFileSnapshot,
         InvalidErrorType,
         JsError(FileSystemJsError),
     }
@@ -55,6 +60,30 @@ mod errors {
         CloseUnknown,
         /// The stream you are trying to close is locked.
         CloseType,
+        /// Thrown if this is an unknown error from open file picker.
+        ShowOpenFilePickerUnknown,
+        /// Thrown if the user dismisses the file picker without selecting a file, or if the user
+        /// agent deems any selected files too sensitive or dangerous.
+        ShowOpenFilePickerAbort,
+        /// Thrown if the call was blocked by the same-origin policy or it was not called via a user
+        /// interaction such as a button press.
+        ShowOpenFilePickerSecurity,
+        /// Thrown if accept types can't be processed.
+        ShowOpenFilePickerType,
+        /// Thrown if this is an unknown error from get file.
+        GetFileUnknown,
+        /// Thrown if the PermissionStatus.state for the handle is not 'granted' in read mode.
+        GetFileNotAllowed,
+        /// Thrown if current entry is not found.
+        GetFileNotFound,
+        /// Thrown if this is an unknown error from reading the file.
+        ReadUnknown,
+        /// Thrown if the file was modified or removed after it was picked.
+        ReadNotReadable,
+        /// Thrown if this is an unknown error from request permission.
+        RequestPermissionUnknown,
+        /// Thrown if permission was requested without a user interaction such as a button press.
+        RequestPermissionSecurity,
     }
 
     impl std::error::Error for FileSystemError {}
@@ -62,6 +91,9 @@ mod errors {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             match self {
                 Self::NotAFileSystemFileHandle => write!(f, "Not a filesystem file handle"),
+                Self::NotAString => write!(f, "Not a string"),
+                Self::NotABool => write!(f, "Not a bool"),
+                Self::NotAFileSnapshot => write!(f, "Not a file snapshot"),
                 Self::InvalidErrorType => write!(f, "Invalid error type"),
                 Self::JsError(err) => write!(f, "JS Error: {}", err),
             }
@@ -91,6 +123,21 @@ mod errors {
                 Self::WriteType => write!(f, "Write type error"),
                 Self::CloseUnknown => write!(f, "Close unknown error"),
                 Self::CloseType => write!(f, "Close type error"),
+                Self::ShowOpenFilePickerUnknown => write!(f, "Show open file picker unknown error"),
+                Self::ShowOpenFilePickerAbort => write!(f, "Show open file picker abort error"),
+                Self::ShowOpenFilePickerSecurity => {
+                    write!(f, "Show open file picker security error")
+                }
+                Self::ShowOpenFilePickerType => write!(f, "Show open file picker type error"),
+                Self::GetFileUnknown => write!(f, "Get file unknown error"),
+                Self::GetFileNotAllowed => write!(f, "Get file not allowed error"),
+                Self::GetFileNotFound => write!(f, "Get file not found error"),
+                Self::ReadUnknown => write!(f, "Read unknown error"),
+                Self::ReadNotReadable => write!(f, "Read not readable error"),
+                Self::RequestPermissionUnknown => write!(f, "Request permission unknown error"),
+                Self::RequestPermissionSecurity => {
+                    write!(f, "Request permission security error")
+                }
             }
         }
     }
@@ -102,48 +149,116 @@ mod inner {
     #[wasm_bindgen(module = "/assets/file_system.js")]
     extern "C" {
         #[wasm_bindgen(catch)]
-        pub async fn save_to_file(handle: JsValue, file_data: String) -> Result<JsValue, JsValue>;
+        pub async fn save_to_file(
+            handle: JsValue,
+            file_data: String,
+            suggested_name: &str,
+        ) -> Result<JsValue, JsValue>;
+        #[wasm_bindgen(catch)]
+        pub async fn open_from_file() -> Result<JsValue, JsValue>;
+        #[wasm_bindgen(catch)]
+        pub async fn read_from_handle(handle: JsValue) -> Result<JsValue, JsValue>;
+        #[wasm_bindgen(catch)]
+        pub async fn verify_permission(handle: JsValue, request: bool) -> Result<JsValue, JsValue>;
     }
 }
 
+/// Writes to the handle, or asks for a file suggesting `suggested_name` if there is none.
 pub async fn save_to_file(
     handle: Option<FileSystemFileHandle>,
     file_data: String,
+    suggested_name: &str,
 ) -> Result<FileSystemFileHandle, FileSystemError> {
     let handle = match handle {
         Some(handle) => handle.into(),
         None => JsValue::NULL,
     };
-    let result = inner::save_to_file(handle, file_data).await;
-
-    use FileSystemError as FSE;
-    use FileSystemJsError as FSJE;
+    let result = inner::save_to_file(handle, file_data, suggested_name).await;
 
     match result {
         Ok(handle) => match handle.dyn_into::<FileSystemFileHandle>() {
             Ok(handle) => Ok(handle),
-            Err(_) => Err(FSE::NotAFileSystemFileHandle),
+            Err(_) => Err(FileSystemError::NotAFileSystemFileHandle),
         },
-        Err(err) => match err.as_f64().map(|x| x as u32) {
-            Some(0) => Err(FSE::JsError(FSJE::UnknownError)),
-            Some(1) => Err(FSE::JsError(FSJE::ShowSaveFilePickerUnknown)),
-            Some(2) => Err(FSE::JsError(FSJE::ShowSaveFilePickerAbort)),
-            Some(3) => Err(FSE::JsError(FSJE::ShowSaveFilePickerSecurity)),
-            Some(4) => Err(FSE::JsError(FSJE::ShowSaveFilePickerType)),
-            Some(5) => Err(FSE::JsError(FSJE::CreateWritableUnknown)),
-            Some(6) => Err(FSE::JsError(FSJE::CreateWritableNotAllowed)),
-            Some(7) => Err(FSE::JsError(FSJE::CreateWritableNotAllowed)),
-            Some(8) => Err(FSE::JsError(FSJE::CreateWritableNoModificationAllowed)),
-            Some(9) => Err(FSE::JsError(FSJE::CreateWritableAbort)),
-            Some(10) => Err(FSE::JsError(FSJE::WriteUnknown)),
-            Some(11) => Err(FSE::JsError(FSJE::WriteNotAllowed)),
-            Some(12) => Err(FSE::JsError(FSJE::WriteQuotaExceeded)),
-            Some(13) => Err(FSE::JsError(FSJE::WriteType)),
-            Some(14) => Err(FSE::JsError(FSJE::CloseUnknown)),
-            Some(15) => Err(FSE::JsError(FSJE::CloseType)),
-            // Explicit remaining case
-            Some(_) => Err(FSE::InvalidErrorType),
-            None => Err(FSE::InvalidErrorType),
+        Err(err) => Err(js_error(err)),
+    }
+}
+
+/// Lets the user pick a file and returns its contents.
+pub async fn open_from_file() -> Result<String, FileSystemError> {
+    let result = inner::open_from_file().await;
+
+    match result {
+        Ok(file_data) => match file_data.as_string() {
+            Some(file_data) => Ok(file_data),
+            None => Err(FileSystemError::NotAString),
         },
+        Err(err) => Err(js_error(err)),
+    }
+}
+
+/// Reads the file behind the handle, without asking for a file.
+pub async fn read_from_handle(
+    handle: &FileSystemFileHandle,
+) -> Result<FileSnapshot, FileSystemError> {
+    let result = inner::read_from_handle(handle.clone().into()).await;
+
+    match result {
+        Ok(snapshot) => {
+            serde_wasm_bindgen::from_value(snapshot).map_err(|_| FileSystemError::NotAFileSnapshot)
+        }
+        Err(err) => Err(js_error(err)),
+    }
+}
+
+/// Whether the handle can be written to. Permission lapses after a reload, `request` asks for it
+/// again, which only works during a user interaction.
+pub async fn verify_permission(
+    handle: &FileSystemFileHandle,
+    request: bool,
+) -> Result<bool, FileSystemError> {
+    let result = inner::verify_permission(handle.clone().into(), request).await;
+
+    match result {
+        Ok(granted) => granted.as_bool().ok_or(FileSystemError::NotABool),
+        Err(err) => Err(js_error(err)),
+    }
+}
+
+fn js_error(err: JsValue) -> FileSystemError {
+    use FileSystemError as FSE;
+    use FileSystemJsError as FSJE;
+
+    match err.as_f64().map(|x| x as u32) {
+        Some(0) => FSE::JsError(FSJE::UnknownError),
+        Some(1) => FSE::JsError(FSJE::ShowSaveFilePickerUnknown),
+        Some(2) => FSE::JsError(FSJE::ShowSaveFilePickerAbort),
+        Some(3) => FSE::JsError(FSJE::ShowSaveFilePickerSecurity),
+        Some(4) => FSE::JsError(FSJE::ShowSaveFilePickerType),
+        Some(5) => FSE::JsError(FSJE::CreateWritableUnknown),
+        Some(6) => FSE::JsError(FSJE::CreateWritableNotAllowed),
+        Some(7) => FSE::JsError(FSJE::CreateWritableNotFound),
+        Some(8) => FSE::JsError(FSJE::CreateWritableNoModificationAllowed),
+        Some(9) => FSE::JsError(FSJE::CreateWritableAbort),
+        Some(10) => FSE::JsError(FSJE::WriteUnknown),
+        Some(11) => FSE::JsError(FSJE::WriteNotAllowed),
+        Some(12) => FSE::JsError(FSJE::WriteQuotaExceeded),
+        Some(13) => FSE::JsError(FSJE::WriteType),
+        Some(14) => FSE::JsError(FSJE::CloseUnknown),
+        Some(15) => FSE::JsError(FSJE::CloseType),
+        Some(16) => FSE::JsError(FSJE::ShowOpenFilePickerUnknown),
+        Some(17) => FSE::JsError(FSJE::ShowOpenFilePickerAbort),
+        Some(18) => FSE::JsError(FSJE::ShowOpenFilePickerSecurity),
+        Some(19) => FSE::JsError(FSJE::ShowOpenFilePickerType),
+        Some(20) => FSE::JsError(FSJE::GetFileUnknown),
+        Some(21) => FSE::JsError(FSJE::GetFileNotAllowed),
+        Some(22) => FSE::JsError(FSJE::GetFileNotFound),
+        Some(23) => FSE::JsError(FSJE::ReadUnknown),
+        Some(24) => FSE::JsError(FSJE::ReadNotReadable),
+        Some(25) => FSE::JsError(FSJE::RequestPermissionUnknown),
+        Some(26) => FSE::JsError(FSJE::RequestPermissionSecurity),
+        // Explicit remaining case
+        Some(_) => FSE::InvalidErrorType,
+        None => FSE::InvalidErrorType,
     }
 }
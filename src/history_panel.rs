@@ -0,0 +1,157 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::{
+    arena::ArenaId,
+    button::{Button, ButtonSize},
+    diff::{diff, Diff},
+    store::{Bookmark, HistoryEntry},
+    time, Action,
+};
+
+/// A bookmark and its [`HistoryEntry`]s, loaded from storage when the panel is opened
+pub struct BookmarkHistory {
+    pub id: ArenaId<Bookmark>,
+    pub bookmark: Bookmark,
+    pub entries: Vec<HistoryEntry>,
+}
+
+/// A change, from the entry's values to the next entry's or the bookmark's
+struct Change {
+    entry: HistoryEntry,
+    /// Only fields that changed
+    fields: Vec<(&'static str, Vec<Diff>)>,
+}
+
+impl BookmarkHistory {
+    /// Newest first
+    fn changes(&self) -> Vec<Change> {
+        let bookmark = &self.bookmark;
+        let afters = self
+            .entries
+            .iter()
+            .skip(1)
+            .map(|x| [x.title.clone(), x.link.clone(), x.note.clone()])
+            .chain(std::iter::once([
+                bookmark.title.clone(),
+                bookmark.link.clone(),
+                bookmark.note.clone(),
+            ]));
+
+        let mut changes: Vec<Change> = self
+            .entries
+            .iter()
+            .zip(afters)
+            .map(|(entry, after)| {
+                let before: [Rc<str>; 3] =
+                    [entry.title.clone(), entry.link.clone(), entry.note.clone()];
+                let fields = ["Title", "Link", "Note"]
+                    .into_iter()
+                    .zip(before.iter().zip(after.iter()))
+                    .filter(|(_, (before, after))| before != after)
+                    .map(|(name, (before, after))| (name, diff(before, after)))
+                    .collect();
+                Change {
+                    entry: entry.clone(),
+                    fields,
+                }
+            })
+            .collect();
+        changes.reverse();
+        changes
+    }
+}
+
+/// Class and text of a part
+fn styled(part: Diff) -> (&'static str, String) {
+    match part {
+        Diff::Same(text) => ("", text),
+        Diff::Removed(text) => ("bg-red-100 text-red-700 line-through", text),
+        Diff::Added(text) => ("bg-green-100 text-green-700", text),
+    }
+}
+
+#[component]
+pub fn HistoryPanel(history: Signal<Option<BookmarkHistory>>) -> Element {
+    let coroutine = use_coroutine_handle::<Action>();
+
+    let mut history = history;
+    let Some((id, bookmark, changes)) = history
+        .read()
+        .as_ref()
+        .map(|x| (x.id, x.bookmark.clone(), x.changes()))
+    else {
+        return None;
+    };
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black/25 flex items-center justify-center",
+            div {
+                class: "bg-primary w-[48rem] max-h-[80vh] p-8 flex flex-col gap-6 shadow-md rounded-xl",
+                h2 {
+                    class: "text-3xl font-bold break-words",
+                    "History of {bookmark.title}"
+                }
+                div {
+                    class: "flex-1 overflow-y-auto flex flex-col gap-2",
+                    if changes.is_empty() {
+                        span {
+                            class: "text-gray-500",
+                            "Not changed since it was created"
+                        }
+                    }
+                    for (index, change) in changes.into_iter().enumerate() {
+                        div {
+                            key: "{index}",
+                            class: "flex flex-col gap-1 px-4 py-2 border-b border-gray-200 break-words",
+                            div {
+                                class: "flex items-center gap-2",
+                                span {
+                                    class: "flex-1 text-sm text-gray-500",
+                                    "Changed {time::format(change.entry.changed_at)}"
+                                }
+                                Button {
+                                    value: "Restore previous",
+                                    size: ButtonSize::Small,
+                                    disabled: change.entry.is_same(&bookmark),
+                                    onclick: {
+                                        let entry = change.entry.clone();
+                                        move |_| {
+                                            coroutine.send(Action::RestoreVersion {
+                                                id,
+                                                entry: entry.clone(),
+                                            })
+                                        }
+                                    },
+                                }
+                            }
+                            for (name, parts) in change.fields {
+                                div {
+                                    key: "{name}",
+                                    class: "text-sm",
+                                    span {
+                                        class: "font-bold mr-2",
+                                        "{name}"
+                                    }
+                                    for (part_index, (class, text)) in parts.into_iter().map(styled).enumerate() {
+                                        span { key: "{part_index}", class, "{text}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "flex gap-4 justify-end",
+                    Button {
+                        value: "Close",
+                        size: ButtonSize::Big,
+                        onclick: move |_| history.set(None),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+//! Formats bookmarks can be imported from. Each one has an [`Importer`], [`detect`] picks the
+//! first one that recognizes the file's contents.
+
+use std::rc::Rc;
+
+use serde::{de::IgnoredAny, Deserialize};
+
+use crate::{
+    link,
+    local_file::FromLocalFile,
+    netscape,
+    store::{normalize_tag, Bookmark, BookmarkId, Collection},
+    time::Timestamp,
+};
+
+pub use errors::*;
+
+mod csv;
+mod firefox;
+mod pinboard;
+mod pocket;
+mod raindrop;
+
+mod errors {
+    use crate::local_file::LocalFileError;
+
+    #[derive(Debug)]
+    pub enum ImportError {
+        Json(serde_json::Error),
+        LocalFile(LocalFileError),
+        /// No importer recognized the file
+        UnknownFormat,
+    }
+
+    impl std::error::Error for ImportError {}
+    impl std::fmt::Display for ImportError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::Json(err) => write!(f, "Invalid file: {}", err),
+                Self::LocalFile(err) => write!(f, "{}", err),
+                Self::UnknownFormat => write!(f, "Unknown file format"),
+            }
+        }
+    }
+    impl From<serde_json::Error> for ImportError {
+        fn from(err: serde_json::Error) -> Self {
+            Self::Json(err)
+        }
+    }
+    impl From<LocalFileError> for ImportError {
+        fn from(err: LocalFileError) -> Self {
+            Self::LocalFile(err)
+        }
+    }
+}
+
+pub trait Importer {
+    /// Shown in the import preview
+    fn name(&self) -> &'static str;
+    /// Whether the file looks like it is in this format, it may still fail to import
+    fn detect(&self, file_data: &str) -> bool;
+    /// Links are saved as [`link::parse`] returns them with `schemes`, bookmarks whose link it
+    /// refuses are left out
+    fn import(&self, file_data: &str, schemes: &[String]) -> Result<ImportedFile, ImportError>;
+}
+
+/// What an [`Importer`] read. Only Librarian files have collections, other formats turn folders
+/// into tags.
+pub struct ImportedFile {
+    pub bookmarks: Vec<Bookmark>,
+    pub collections: Vec<Collection>,
+}
+
+impl From<Vec<Bookmark>> for ImportedFile {
+    fn from(bookmarks: Vec<Bookmark>) -> Self {
+        Self {
+            bookmarks,
+            collections: Vec::new(),
+        }
+    }
+}
+
+/// In the order they are tried, more specific formats first
+pub const IMPORTERS: &[&dyn Importer] = &[
+    &LocalFileImporter,
+    &firefox::Firefox,
+    &pinboard::Pinboard,
+    &NetscapeImporter,
+    &pocket::PocketHtml,
+    &pocket::PocketCsv,
+    &raindrop::Raindrop,
+];
+
+pub fn detect(file_data: &str) -> Option<&'static dyn Importer> {
+    IMPORTERS.iter().copied().find(|x| x.detect(file_data))
+}
+
+/// Librarian's own file, see [`crate::local_file`]
+struct LocalFileImporter;
+
+impl Importer for LocalFileImporter {
+    fn name(&self) -> &'static str {
+        "Librarian"
+    }
+    fn detect(&self, file_data: &str) -> bool {
+        #[derive(Deserialize)]
+        struct Shape {
+            #[allow(dead_code)]
+            version: u64,
+            #[allow(dead_code)]
+            bookmarks: IgnoredAny,
+        }
+
+        file_data.trim_start().starts_with('{') && serde_json::from_str::<Shape>(file_data).is_ok()
+    }
+    fn import(&self, file_data: &str, schemes: &[String]) -> Result<ImportedFile, ImportError> {
+        let file = file_data.parse::<FromLocalFile>()?;
+        let bookmarks = file
+            .bookmarks
+            .into_iter()
+            .filter_map(|mut bookmark| {
+                bookmark.link = Rc::from(link::parse(&bookmark.link, schemes).ok()?);
+                Some(bookmark)
+            })
+            .collect();
+
+        Ok(ImportedFile {
+            bookmarks,
+            collections: file.collections,
+        })
+    }
+}
+
+/// `bookmarks.html` of browsers, see [`crate::netscape`]
+struct NetscapeImporter;
+
+impl Importer for NetscapeImporter {
+    fn name(&self) -> &'static str {
+        "Browser HTML"
+    }
+    fn detect(&self, file_data: &str) -> bool {
+        netscape::is_netscape(file_data)
+    }
+    fn import(&self, file_data: &str, schemes: &[String]) -> Result<ImportedFile, ImportError> {
+        Ok(netscape::parse(file_data, schemes).into())
+    }
+}
+
+/// Fields importers map their format onto, missing timestamps are 0
+#[derive(Default)]
+struct Imported<'a> {
+    title: &'a str,
+    link: &'a str,
+    note: &'a str,
+    tags: Vec<&'a str>,
+    created_at: Option<Timestamp>,
+    updated_at: Option<Timestamp>,
+    last_visited_at: Option<Timestamp>,
+}
+
+impl Imported<'_> {
+    /// None if [`link::parse`] refuses the link with `schemes`. Link is the title if there is no
+    /// title.
+    fn into_bookmark(self, schemes: &[String]) -> Option<Bookmark> {
+        let link = link::parse(self.link, schemes).ok()?;
+
+        let title = match self.title.trim() {
+            "" => self.link.trim(),
+            title => title,
+        };
+
+        let mut tags: Vec<Rc<str>> = Vec::new();
+        for tag in self.tags.into_iter().map(normalize_tag) {
+            if !tag.is_empty() && !tags.iter().any(|x| **x == *tag) {
+                tags.push(Rc::from(tag));
+            }
+        }
+
+        let created_at = self.created_at.unwrap_or_default();
+
+        Some(Bookmark {
+            id: BookmarkId::new(),
+            title: Rc::from(title),
+            link: Rc::from(link),
+            note: Rc::from(self.note.trim()),
+            created_at,
+            updated_at: self.updated_at.unwrap_or(created_at),
+            last_visited_at: self.last_visited_at,
+            deleted_at: None,
+            collection: None,
+            tags,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect, Importer, LocalFileImporter};
+    use crate::{
+        local_file::{ToLocalFile, LOCAL_FILE_VERSION},
+        settings::Settings,
+        store::Bookmark,
+    };
+
+    #[test]
+    fn detects_formats() {
+        let name = |file_data: &str| detect(file_data).map(|x| x.name());
+
+        assert_eq!(
+            name(r#"{ "version": 1, "bookmarks": [] }"#),
+            Some("Librarian")
+        );
+        assert_eq!(
+            name(r#"{ "guid": "root________", "type": "text/x-moz-place-container" }"#),
+            Some("Firefox")
+        );
+        assert_eq!(
+            name(r#"[{ "href": "https://a.com", "description": "a" }]"#),
+            Some("Pinboard")
+        );
+        assert_eq!(
+            name("<!DOCTYPE NETSCAPE-Bookmark-file-1>\n<DL><p></DL>"),
+            Some("Browser HTML")
+        );
+        assert_eq!(
+            name("<!DOCTYPE html>\n<html><head><title>Pocket Export</title></head></html>"),
+            Some("Pocket")
+        );
+        assert_eq!(
+            name("title,url,time_added,tags,status\n"),
+            Some("Pocket CSV")
+        );
+        assert_eq!(
+            name("id,title,note,excerpt,url,folder,tags,created,cover,highlights,favorite\n"),
+            Some("Raindrop")
+        );
+        assert_eq!(name("just some text"), None);
+        assert_eq!(name(r#"{ "bookmarks": [] }"#), None);
+    }
+
+    #[test]
+    fn saves_links_as_parsed() {
+        let schemes = Settings::default().allowed_schemes;
+        let links = |file_data: &str| -> Vec<String> {
+            let importer = detect(file_data).unwrap();
+            let file = importer.import(file_data, &schemes).unwrap();
+            file.bookmarks.iter().map(|x| x.link.to_string()).collect()
+        };
+
+        let pinboard = r#"[
+            { "href": "rust-lang.org", "description": "Rust" },
+            { "href": "https://bücher.de", "description": "" },
+            { "href": "javascript:alert(1)", "description": "Bookmarklet" }
+        ]"#;
+        assert_eq!(
+            links(pinboard),
+            ["https://rust-lang.org/", "https://xn--bcher-kva.de/"]
+        );
+
+        let bookmarks = [
+            Bookmark::new("Rust", "https://www.rust-lang.org/", "", 1),
+            Bookmark::new("Bookmarklet", "javascript:alert(1)", "", 1),
+        ];
+        let librarian = serde_json::to_string(&ToLocalFile {
+            version: LOCAL_FILE_VERSION,
+            bookmarks: bookmarks.iter().collect(),
+            collections: Vec::new(),
+        })
+        .unwrap();
+        assert!(LocalFileImporter.detect(&librarian));
+        assert_eq!(links(&librarian), ["https://www.rust-lang.org/"]);
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+/// Rows of fields. Quoted fields can have commas, newlines and `""` for a quote.
+pub fn parse(data: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut is_quoted = false;
+
+    let mut characters = data.chars().peekable();
+    while let Some(character) = characters.next() {
+        match (is_quoted, character) {
+            (true, '"') if characters.peek() == Some(&'"') => {
+                characters.next();
+                field.push('"');
+            }
+            (true, '"') => is_quoted = false,
+            (true, character) => field.push(character),
+            (false, '"') => is_quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (false, character) => field.push(character),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// Rows after the header, keyed by lowercased header names
+pub fn records(data: &str) -> Vec<HashMap<String, String>> {
+    let mut rows = parse(data.trim_start_matches('\u{feff}')).into_iter();
+    let Some(header) = rows.next() else {
+        return Vec::new();
+    };
+    let header: Vec<String> = header.iter().map(|x| x.trim().to_lowercase()).collect();
+
+    rows.filter(|row| row.iter().any(|x| !x.is_empty()))
+        .map(|row| header.iter().cloned().zip(row).collect())
+        .collect()
+}
+
+/// Lowercased names in the first line
+pub fn header(data: &str) -> Vec<String> {
+    let first_line = data.trim_start_matches('\u{feff}').lines().next();
+    parse(first_line.unwrap_or_default())
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .map(|x| x.trim().to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{header, parse, records};
+
+    #[test]
+    fn parses_quoted_fields() {
+        let rows = parse("a,\"b, c\",\"d \"\"e\"\"\"\r\n\"multi\nline\",,f\n");
+        assert_eq!(
+            rows,
+            vec![vec!["a", "b, c", "d \"e\""], vec!["multi\nline", "", "f"],]
+        );
+    }
+
+    #[test]
+    fn reads_records_by_header() {
+        let records = records("\u{feff}Title,URL\nRust,https://www.rust-lang.org/\n\n");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["title"], "Rust");
+        assert_eq!(records[0]["url"], "https://www.rust-lang.org/");
+        assert_eq!(header("Title, URL\nx,y"), vec!["title", "url"]);
+    }
+}
@@ -0,0 +1,184 @@
+//! Firefox's JSON backup, a tree of places starting at the root folder.
+
+use serde::Deserialize;
+
+use super::{ImportError, Imported, ImportedFile, Importer};
+use crate::{store::Bookmark, time::Timestamp};
+
+pub struct Firefox;
+
+const CONTAINER: &str = "text/x-moz-place-container";
+const PLACE: &str = "text/x-moz-place";
+const DESCRIPTION: &str = "bookmarkProperties/description";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Place {
+    #[serde(default)]
+    title: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+    /// Set on folders Firefox manages, like the toolbar
+    #[serde(default)]
+    root: Option<String>,
+    #[serde(default)]
+    uri: String,
+    /// Microseconds
+    #[serde(default)]
+    date_added: Option<Timestamp>,
+    #[serde(default)]
+    last_modified: Option<Timestamp>,
+    /// Comma separated
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    annos: Vec<Annotation>,
+    #[serde(default)]
+    children: Vec<Place>,
+}
+
+#[derive(Deserialize)]
+struct Annotation {
+    name: String,
+    #[serde(default)]
+    value: serde_json::Value,
+}
+
+fn timestamp(microseconds: Option<Timestamp>) -> Option<Timestamp> {
+    microseconds.map(|x| x / 1000).filter(|x| *x != 0)
+}
+
+impl Importer for Firefox {
+    fn name(&self) -> &'static str {
+        "Firefox"
+    }
+    fn detect(&self, file_data: &str) -> bool {
+        #[derive(Deserialize)]
+        struct Shape {
+            #[serde(rename = "type")]
+            kind: String,
+        }
+
+        file_data.trim_start().starts_with('{')
+            && serde_json::from_str::<Shape>(file_data).is_ok_and(|x| x.kind == CONTAINER)
+    }
+    /// Folders become tags, except the ones Firefox manages
+    fn import(&self, file_data: &str, schemes: &[String]) -> Result<ImportedFile, ImportError> {
+        let root: Place = serde_json::from_str(file_data)?;
+
+        let mut bookmarks = Vec::new();
+        let mut folders = Vec::new();
+        collect(&root, &mut folders, &mut bookmarks, schemes);
+
+        Ok(bookmarks.into())
+    }
+}
+
+fn collect<'a>(
+    place: &'a Place,
+    folders: &mut Vec<&'a str>,
+    bookmarks: &mut Vec<Bookmark>,
+    schemes: &[String],
+) {
+    match place.kind.as_str() {
+        CONTAINER => {
+            let is_folder = place.root.is_none() && !place.title.is_empty();
+            if is_folder {
+                folders.push(&place.title);
+            }
+            for child in place.children.iter() {
+                collect(child, folders, bookmarks, schemes);
+            }
+            if is_folder {
+                folders.pop();
+            }
+        }
+        PLACE => {
+            let note = place
+                .annos
+                .iter()
+                .find(|x| x.name == DESCRIPTION)
+                .and_then(|x| x.value.as_str())
+                .unwrap_or_default();
+
+            let bookmark = Imported {
+                title: &place.title,
+                link: &place.uri,
+                note,
+                tags: place
+                    .tags
+                    .split(',')
+                    .chain(folders.iter().copied())
+                    .collect(),
+                created_at: timestamp(place.date_added),
+                updated_at: timestamp(place.last_modified),
+                ..Default::default()
+            }
+            .into_bookmark(schemes);
+
+            bookmarks.extend(bookmark);
+        }
+        // Separators
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Firefox;
+    use crate::{import::Importer, settings::Settings};
+
+    #[test]
+    fn imports_backup() {
+        let file_data = r#"{
+            "guid": "root________", "title": "", "index": 0, "dateAdded": 1717243200000000,
+            "lastModified": 1717243200000000, "id": 1, "typeCode": 2,
+            "type": "text/x-moz-place-container", "root": "placesRoot",
+            "children": [
+                {
+                    "guid": "toolbar_____", "title": "toolbar", "type": "text/x-moz-place-container",
+                    "root": "toolbarFolder",
+                    "children": [
+                        {
+                            "guid": "a", "title": "Rust", "type": "text/x-moz-place",
+                            "uri": "https://www.rust-lang.org/", "dateAdded": 1717243200000000,
+                            "lastModified": 1717329600000000, "tags": "rust,language",
+                            "annos": [
+                                { "name": "bookmarkProperties/description", "value": "A language" }
+                            ]
+                        },
+                        {
+                            "guid": "b", "title": "Web Dev", "type": "text/x-moz-place-container",
+                            "children": [
+                                {
+                                    "guid": "c", "title": "Dioxus", "type": "text/x-moz-place",
+                                    "uri": "https://dioxuslabs.com", "dateAdded": 1717250400000000
+                                },
+                                { "guid": "d", "type": "text/x-moz-place-separator" },
+                                {
+                                    "guid": "e", "title": "Recent", "type": "text/x-moz-place",
+                                    "uri": "place:sort=8"
+                                }
+                            ]
+                        }
+                    ]
+                }
+            ]
+        }"#;
+        let bookmarks = Firefox
+            .import(file_data, &Settings::default().allowed_schemes)
+            .unwrap()
+            .bookmarks;
+
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(&*bookmarks[0].note, "A language");
+        assert_eq!(bookmarks[0].created_at, 1717243200000);
+        assert_eq!(bookmarks[0].updated_at, 1717329600000);
+        let tags: Vec<&str> = bookmarks[0].tags.iter().map(|x| &**x).collect();
+        assert_eq!(tags, vec!["rust", "language"]);
+
+        let tags: Vec<&str> = bookmarks[1].tags.iter().map(|x| &**x).collect();
+        assert_eq!(tags, vec!["web-dev"]);
+        assert_eq!(bookmarks[1].updated_at, bookmarks[1].created_at);
+    }
+}
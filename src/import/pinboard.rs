@@ -0,0 +1,87 @@
+//! Pinboard's JSON export, an array of posts.
+
+use serde::Deserialize;
+
+use super::{ImportError, Imported, ImportedFile, Importer};
+use crate::{store::Bookmark, time};
+
+pub struct Pinboard;
+
+#[derive(Deserialize)]
+struct Post {
+    href: String,
+    description: String,
+    #[serde(default)]
+    extended: String,
+    /// ISO 8601
+    #[serde(default)]
+    time: String,
+    /// Space separated
+    #[serde(default)]
+    tags: String,
+}
+
+impl Importer for Pinboard {
+    fn name(&self) -> &'static str {
+        "Pinboard"
+    }
+    fn detect(&self, file_data: &str) -> bool {
+        file_data.trim_start().starts_with('[')
+            && serde_json::from_str::<Vec<Post>>(file_data).is_ok()
+    }
+    fn import(&self, file_data: &str, schemes: &[String]) -> Result<ImportedFile, ImportError> {
+        let posts: Vec<Post> = serde_json::from_str(file_data)?;
+
+        let bookmarks: Vec<Bookmark> = posts
+            .iter()
+            .filter_map(|post| {
+                Imported {
+                    title: &post.description,
+                    link: &post.href,
+                    note: &post.extended,
+                    tags: post.tags.split_whitespace().collect(),
+                    created_at: time::parse_iso8601(&post.time),
+                    ..Default::default()
+                }
+                .into_bookmark(schemes)
+            })
+            .collect();
+
+        Ok(bookmarks.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pinboard;
+    use crate::{import::Importer, settings::Settings};
+
+    #[test]
+    fn imports_posts() {
+        let file_data = r#"[
+            {
+                "href": "https://www.rust-lang.org/",
+                "description": "Rust",
+                "extended": "A language",
+                "meta": "abc",
+                "hash": "def",
+                "time": "2024-06-01T12:00:00Z",
+                "shared": "no",
+                "toread": "no",
+                "tags": "rust Language"
+            },
+            { "href": "javascript:void(0)", "description": "Bookmarklet" }
+        ]"#;
+        let bookmarks = Pinboard
+            .import(file_data, &Settings::default().allowed_schemes)
+            .unwrap()
+            .bookmarks;
+
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(&*bookmarks[0].title, "Rust");
+        assert_eq!(&*bookmarks[0].note, "A language");
+        assert_eq!(bookmarks[0].created_at, 1717243200000);
+        let tags: Vec<&str> = bookmarks[0].tags.iter().map(|x| &**x).collect();
+        assert_eq!(tags, vec!["rust", "language"]);
+    }
+}
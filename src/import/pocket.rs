@@ -0,0 +1,140 @@
+//! Pocket's exports, the older `ril_export.html` and the newer CSV.
+
+use super::{csv, ImportError, Imported, ImportedFile, Importer};
+use crate::{
+    netscape,
+    store::Bookmark,
+    time::{Timestamp, SECOND},
+};
+
+pub struct PocketHtml;
+pub struct PocketCsv;
+
+/// Unix seconds in both exports
+fn timestamp(seconds: &str) -> Option<Timestamp> {
+    seconds.trim().parse::<Timestamp>().ok().map(|x| x * SECOND)
+}
+
+impl Importer for PocketHtml {
+    fn name(&self) -> &'static str {
+        "Pocket"
+    }
+    fn detect(&self, file_data: &str) -> bool {
+        file_data.contains("<title>Pocket Export</title>")
+    }
+    /// Every `<a>` is a bookmark, tags are comma separated
+    fn import(&self, file_data: &str, schemes: &[String]) -> Result<ImportedFile, ImportError> {
+        let mut bookmarks = Vec::new();
+
+        let mut rest = file_data;
+        while let Some(start) = rest.find("<a ") {
+            rest = &rest[start + 3..];
+            let Some(end) = rest.find('>') else {
+                break;
+            };
+            let attributes = netscape::parse_attributes(&rest[..end]);
+            rest = &rest[end + 1..];
+
+            let title_end = rest.find("</a>").unwrap_or(rest.len());
+            let title = netscape::decode(&rest[..title_end]);
+            rest = &rest[title_end..];
+
+            let get = |key: &str| attributes.get(key).map(String::as_str).unwrap_or_default();
+            let bookmark = Imported {
+                title: &title,
+                link: get("HREF"),
+                tags: get("TAGS").split(',').collect(),
+                created_at: timestamp(get("TIME_ADDED")),
+                ..Default::default()
+            }
+            .into_bookmark(schemes);
+
+            bookmarks.extend(bookmark);
+        }
+
+        Ok(bookmarks.into())
+    }
+}
+
+impl Importer for PocketCsv {
+    fn name(&self) -> &'static str {
+        "Pocket CSV"
+    }
+    fn detect(&self, file_data: &str) -> bool {
+        let header = csv::header(file_data);
+        ["title", "url", "time_added"]
+            .iter()
+            .all(|x| header.iter().any(|y| y == x))
+    }
+    /// Tags are separated with `|`
+    fn import(&self, file_data: &str, schemes: &[String]) -> Result<ImportedFile, ImportError> {
+        let bookmarks: Vec<Bookmark> = csv::records(file_data)
+            .iter()
+            .filter_map(|record| {
+                let get = |key: &str| record.get(key).map(String::as_str).unwrap_or_default();
+                Imported {
+                    title: get("title"),
+                    link: get("url"),
+                    tags: get("tags").split('|').collect(),
+                    created_at: timestamp(get("time_added")),
+                    ..Default::default()
+                }
+                .into_bookmark(schemes)
+            })
+            .collect();
+
+        Ok(bookmarks.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PocketCsv, PocketHtml};
+    use crate::{import::Importer, settings::Settings};
+
+    #[test]
+    fn imports_html() {
+        let file_data = r#"<!DOCTYPE html>
+<html>
+<head><title>Pocket Export</title></head>
+<body>
+<h1>Unread</h1>
+<ul>
+<li><a href="https://www.rust-lang.org/" time_added="1717243200" tags="rust,language">Rust &amp; Cargo</a></li>
+</ul>
+<h1>Read Archive</h1>
+<ul>
+<li><a href="https://dioxuslabs.com" time_added="1717250400" tags="">Dioxus</a></li>
+</ul>
+</body>
+</html>"#;
+        let bookmarks = PocketHtml
+            .import(file_data, &Settings::default().allowed_schemes)
+            .unwrap()
+            .bookmarks;
+
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(&*bookmarks[0].title, "Rust & Cargo");
+        assert_eq!(bookmarks[0].created_at, 1717243200000);
+        assert_eq!(bookmarks[0].tags.len(), 2);
+        assert_eq!(&*bookmarks[1].link, "https://dioxuslabs.com/");
+        assert!(bookmarks[1].tags.is_empty());
+    }
+
+    #[test]
+    fn imports_csv() {
+        let file_data = "title,url,time_added,tags,status\n\
+            \"Rust, the language\",https://www.rust-lang.org/,1717243200,rust|language,unread\n\
+            Dioxus,https://dioxuslabs.com,1717250400,,archive\n";
+        let bookmarks = PocketCsv
+            .import(file_data, &Settings::default().allowed_schemes)
+            .unwrap()
+            .bookmarks;
+
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(&*bookmarks[0].title, "Rust, the language");
+        let tags: Vec<&str> = bookmarks[0].tags.iter().map(|x| &**x).collect();
+        assert_eq!(tags, vec!["rust", "language"]);
+        assert_eq!(bookmarks[1].created_at, 1717250400000);
+    }
+}
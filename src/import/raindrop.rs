@@ -0,0 +1,69 @@
+//! Raindrop.io's CSV export.
+
+use super::{csv, ImportError, Imported, ImportedFile, Importer};
+use crate::{store::Bookmark, time};
+
+pub struct Raindrop;
+
+/// Collection every bookmark without one is in
+const UNSORTED: &str = "Unsorted";
+
+impl Importer for Raindrop {
+    fn name(&self) -> &'static str {
+        "Raindrop"
+    }
+    fn detect(&self, file_data: &str) -> bool {
+        let header = csv::header(file_data);
+        ["id", "title", "note", "excerpt", "url", "folder"]
+            .iter()
+            .all(|x| header.iter().any(|y| y == x))
+    }
+    /// Tags are comma separated, folders are `/` separated and become tags too
+    fn import(&self, file_data: &str, schemes: &[String]) -> Result<ImportedFile, ImportError> {
+        let bookmarks: Vec<Bookmark> = csv::records(file_data)
+            .iter()
+            .filter_map(|record| {
+                let get = |key: &str| record.get(key).map(String::as_str).unwrap_or_default();
+
+                let folders = get("folder").split('/').filter(|x| *x != UNSORTED);
+                let tags = get("tags").split(',').chain(folders).collect();
+
+                Imported {
+                    title: get("title"),
+                    link: get("url"),
+                    note: get("note"),
+                    tags,
+                    created_at: time::parse_iso8601(get("created")),
+                    ..Default::default()
+                }
+                .into_bookmark(schemes)
+            })
+            .collect();
+
+        Ok(bookmarks.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Raindrop;
+    use crate::{import::Importer, settings::Settings};
+
+    #[test]
+    fn imports_csv() {
+        let file_data = "id,title,note,excerpt,url,folder,tags,created,cover,highlights,favorite\n\
+            1,Rust,Read later,A language,https://www.rust-lang.org/,Dev/Rust,\"rust, language\",2024-06-01T12:00:00.000Z,,,false\n\
+            2,Dioxus,,,https://dioxuslabs.com,Unsorted,,2024-06-01T14:00:00.000Z,,,true\n";
+        let bookmarks = Raindrop
+            .import(file_data, &Settings::default().allowed_schemes)
+            .unwrap()
+            .bookmarks;
+
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(&*bookmarks[0].note, "Read later");
+        assert_eq!(bookmarks[0].created_at, 1717243200000);
+        let tags: Vec<&str> = bookmarks[0].tags.iter().map(|x| &**x).collect();
+        assert_eq!(tags, vec!["rust", "language", "dev"]);
+        assert!(bookmarks[1].tags.is_empty());
+    }
+}
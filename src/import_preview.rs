@@ -0,0 +1,91 @@
+use dioxus::prelude::*;
+
+use crate::{
+    button::{Button, ButtonSize},
+    store::{Bookmark, Collection},
+    Action,
+};
+
+/// Bookmarks read from a file, waiting to be confirmed before they are imported
+pub struct PendingImport {
+    /// Name of the importer that read the file
+    pub format: &'static str,
+    pub bookmarks: Vec<Bookmark>,
+    pub collections: Vec<Collection>,
+}
+
+#[component]
+pub fn ImportPreview(preview: Signal<Option<PendingImport>>) -> Element {
+    let coroutine = use_coroutine_handle::<Action>();
+
+    let mut preview = preview;
+    let Some((format, bookmarks)) = preview
+        .read()
+        .as_ref()
+        .map(|x| (x.format, x.bookmarks.clone()))
+    else {
+        return None;
+    };
+
+    let count = bookmarks.len();
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black/25 flex items-center justify-center",
+            div {
+                class: "bg-primary w-[48rem] max-h-[80vh] p-8 flex flex-col gap-6 shadow-md rounded-xl",
+                h2 {
+                    class: "text-3xl font-bold",
+                    "Import {count} bookmarks from {format}"
+                }
+                div {
+                    class: "flex-1 overflow-y-auto flex flex-col gap-2",
+                    if bookmarks.is_empty() {
+                        span {
+                            class: "text-gray-500",
+                            "Nothing to import"
+                        }
+                    }
+                    for (index, bookmark) in bookmarks.into_iter().enumerate() {
+                        div {
+                            key: "{index}",
+                            class: "flex flex-col px-4 py-2 border-b border-gray-200 break-words",
+                            span {
+                                class: "text-sm font-bold",
+                                {bookmark.title.to_string()}
+                            }
+                            span {
+                                class: "text-sky-500 text-sm",
+                                {bookmark.link.to_string()}
+                            }
+                            div {
+                                class: "flex flex-wrap gap-2",
+                                for tag in bookmark.tags.iter() {
+                                    span {
+                                        key: "{tag}",
+                                        class: "px-2 rounded-md bg-tertiary text-xs",
+                                        "{tag}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "flex gap-4 justify-end",
+                    Button {
+                        value: "Cancel",
+                        size: ButtonSize::Big,
+                        onclick: move |_| preview.set(None),
+                    }
+                    Button {
+                        value: "Import",
+                        size: ButtonSize::Big,
+                        disabled: count == 0,
+                        onclick: move |_| coroutine.send(Action::ConfirmImport),
+                    }
+                }
+            }
+        }
+    }
+}
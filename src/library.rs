@@ -0,0 +1,101 @@
+//! Libraries are separate sets of bookmarks, each with its own storage, synced file and settings.
+//! The list of them is kept in the default library's storage.
+
+use serde::{Deserialize, Serialize};
+
+/// Name of the database and file from before there were libraries, the default library keeps it
+const DEFAULT_STORAGE_NAME: &str = "librarian";
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LibraryId(u32);
+
+impl LibraryId {
+    pub const DEFAULT: Self = Self(0);
+
+    /// Name of its IndexedDB database, or of its file without the extension
+    pub fn storage_name(&self) -> String {
+        match *self {
+            Self::DEFAULT => DEFAULT_STORAGE_NAME.to_string(),
+            Self(id) => format!("{DEFAULT_STORAGE_NAME}-{id}"),
+        }
+    }
+}
+
+impl std::fmt::Display for LibraryId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Library {
+    pub id: LibraryId,
+    pub name: String,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Libraries {
+    /// In the order they were created, the default library is always first
+    pub libraries: Vec<Library>,
+    /// Opened on start
+    pub current: LibraryId,
+}
+
+impl Libraries {
+    pub const KEY: &'static str = "libraries";
+
+    /// Names are trimmed, an empty name is replaced with a numbered one
+    pub fn create(&mut self, name: &str) -> LibraryId {
+        let id = self
+            .libraries
+            .iter()
+            .map(|x| x.id)
+            .max()
+            .map_or(LibraryId::DEFAULT, |LibraryId(id)| LibraryId(id + 1));
+
+        let name = match name.trim() {
+            "" => format!("Library {}", self.libraries.len() + 1),
+            name => name.to_string(),
+        };
+
+        self.libraries.push(Library { id, name });
+        id
+    }
+    pub fn get(&self, id: LibraryId) -> Option<&Library> {
+        self.libraries.iter().find(|x| x.id == id)
+    }
+}
+
+impl Default for Libraries {
+    fn default() -> Self {
+        Self {
+            libraries: vec![Library {
+                id: LibraryId::DEFAULT,
+                name: "Personal".to_string(),
+            }],
+            current: LibraryId::DEFAULT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Libraries, LibraryId};
+
+    #[test]
+    fn creates_libraries() {
+        let mut libraries = Libraries::default();
+        assert_eq!(LibraryId::DEFAULT.storage_name(), "librarian");
+
+        let work = libraries.create(" Work ");
+        let unnamed = libraries.create("");
+
+        assert_ne!(work, LibraryId::DEFAULT);
+        assert_ne!(work, unnamed);
+        assert_eq!(libraries.get(work).unwrap().name, "Work");
+        assert_eq!(libraries.get(unnamed).unwrap().name, "Library 3");
+        assert_eq!(work.storage_name(), format!("librarian-{work}"));
+    }
+}
@@ -0,0 +1,74 @@
+use dioxus::prelude::*;
+
+use crate::{
+    button::{Button, ButtonSize},
+    library::Libraries,
+    Action,
+};
+
+#[component]
+pub fn LibrarySwitcher(libraries: Libraries) -> Element {
+    let coroutine = use_coroutine_handle::<Action>();
+
+    let mut is_creating = use_signal(|| false);
+    let mut new_name = use_signal(|| String::new());
+
+    let mut create = move || {
+        coroutine.send(Action::CreateLibrary { name: new_name() });
+        new_name.set(String::new());
+        is_creating.set(false);
+    };
+
+    rsx! {
+        span {
+            class: "ml-8 flex items-center gap-2",
+            select {
+                class: "bg-primary px-2 h-8 rounded-md border border-gray-300",
+                onchange: {
+                    let libraries = libraries.clone();
+                    move |ev: FormEvent| {
+                        let library = libraries
+                            .libraries
+                            .iter()
+                            .find(|x| x.id.to_string() == ev.value());
+                        if let Some(library) = library {
+                            coroutine.send(Action::SwitchLibrary { id: library.id });
+                        }
+                    }
+                },
+                for library in libraries.libraries.iter() {
+                    option {
+                        key: "{library.id}",
+                        value: "{library.id}",
+                        selected: library.id == libraries.current,
+                        "{library.name}"
+                    }
+                }
+            }
+            if is_creating() {
+                input {
+                    class: "bg-primary w-32 px-2 h-8 rounded-md border border-gray-300",
+                    placeholder: "Library name",
+                    value: new_name,
+                    oninput: move |ev| new_name.set(ev.value()),
+                    onkeydown: move |ev| {
+                        if ev.key() == Key::Enter {
+                            create();
+                        }
+                    },
+                }
+                Button {
+                    value: "Create",
+                    size: ButtonSize::Small,
+                    onclick: move |_| create(),
+                }
+            } else {
+                Button {
+                    value: "New library",
+                    size: ButtonSize::Small,
+                    onclick: move |_| is_creating.set(true),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! Links typed into the drawer are [`parse`]d into the form that is saved: a URL with an allowed
+//! scheme and an ASCII host, [`display`] turns punycode back into Unicode.
+//!
+//! [`normalize`] gives the canonical form of links, so the same page saved twice can be found.
+//! Only differences that don't change the page are dropped: case of scheme and host, Unicode
+//! hosts, default ports, a trailing slash, tracking parameters and fragments that only scroll.
+
+use url::Url;
+
+pub use errors::*;
+
+mod errors {
+    #[derive(Clone, PartialEq, Debug)]
+    pub enum LinkError {
+        Empty,
+        Whitespace,
+        Url(url::ParseError),
+        /// Typed without a scheme and the host doesn't look like a domain
+        NotADomain,
+        /// Not in [`crate::settings::Settings::allowed_schemes`]
+        Scheme(String),
+    }
+
+    impl std::error::Error for LinkError {}
+    impl std::fmt::Display for LinkError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::Empty => write!(f, "Link is empty"),
+                Self::Whitespace => write!(f, "Links can't contain spaces"),
+                Self::Url(err) => write!(f, "Invalid link: {}", err),
+                Self::NotADomain => write!(f, "Not a domain, add a scheme like https://"),
+                Self::Scheme(scheme) => write!(f, "{}: links are not allowed", scheme),
+            }
+        }
+    }
+    impl From<url::ParseError> for LinkError {
+        fn from(err: url::ParseError) -> Self {
+            Self::Url(err)
+        }
+    }
+}
+
+/// Whether the text before the first `:` is a scheme rather than a host with a port
+fn has_scheme(input: &str) -> bool {
+    if input.contains("://") {
+        return true;
+    }
+    match input.split_once(':') {
+        Some((scheme, rest)) => {
+            scheme.chars().all(|x| x.is_ascii_alphabetic())
+                && !rest.starts_with(|x: char| x.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+/// Link to save from what was typed. Bare domains get `https://`, Unicode hosts are turned into
+/// punycode and `schemes` are the allowed ones, lowercase.
+pub fn parse(input: &str, schemes: &[String]) -> Result<String, LinkError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(LinkError::Empty);
+    }
+    if input.contains(char::is_whitespace) {
+        return Err(LinkError::Whitespace);
+    }
+
+    let url = if has_scheme(input) {
+        Url::parse(input)?
+    } else {
+        let url = Url::parse(&format!("https://{input}"))?;
+        let host = url.host_str().unwrap_or_default();
+        if !host.contains('.') && host != "localhost" {
+            return Err(LinkError::NotADomain);
+        }
+        url
+    };
+
+    if !schemes.iter().any(|x| x == url.scheme()) {
+        return Err(LinkError::Scheme(url.scheme().to_string()));
+    }
+
+    Ok(url.into())
+}
+
+/// Link with its host in Unicode, as typed rather than as saved
+pub fn display(link: &str) -> String {
+    let Ok(url) = Url::parse(link) else {
+        return link.to_string();
+    };
+    let Some(host) = url.host_str().filter(|x| x.contains("xn--")) else {
+        return link.to_string();
+    };
+
+    let (unicode, result) = idna::domain_to_unicode(host);
+    match (result, link.find(host)) {
+        (Ok(()), Some(index)) => {
+            format!(
+                "{}{}{}",
+                &link[..index],
+                unicode,
+                &link[index + host.len()..]
+            )
+        }
+        _ => link.to_string(),
+    }
+}
+
+/// Query parameters that only track where a visit came from, besides `utm_*` ones
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "twclid", "igshid",
+    "mc_cid", "mc_eid", "_hsenc", "_hsmi", "mkt_tok",
+];
+
+fn default_port(scheme: &str) -> Option<&'static str> {
+    match scheme {
+        "http" | "ws" => Some("80"),
+        "https" | "wss" => Some("443"),
+        "ftp" => Some("21"),
+        _ => None,
+    }
+}
+
+fn is_tracking(param: &str) -> bool {
+    let key = param
+        .split('=')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    key.starts_with("utm_") || TRACKING_PARAMS.contains(&key.as_str())
+}
+
+/// Links that only differ in what is dropped here have the same canonical form. Fragments are
+/// kept if they route a single page app, `#/` or `#!`.
+pub fn normalize(link: &str) -> String {
+    let link = link.trim();
+    match Url::parse(link) {
+        Ok(url) => normalize_url(&url),
+        // Saved before links were parsed
+        Err(_) => normalize_text(link),
+    }
+}
+
+/// [`Url`] already lowercases the scheme, puts the host in punycode and drops default ports
+fn normalize_url(url: &Url) -> String {
+    let mut normalized = String::from(url.scheme());
+    normalized.push(':');
+    if let Some(host) = url.host_str() {
+        normalized.push_str("//");
+        if !url.username().is_empty() || url.password().is_some() {
+            normalized.push_str(url.username());
+            if let Some(password) = url.password() {
+                normalized.push(':');
+                normalized.push_str(password);
+            }
+            normalized.push('@');
+        }
+        normalized.push_str(host.trim_end_matches('.'));
+        if let Some(port) = url.port() {
+            normalized.push(':');
+            normalized.push_str(&port.to_string());
+        }
+    }
+    push_path(&mut normalized, url.path(), url.query(), url.fragment());
+
+    normalized
+}
+
+fn normalize_text(link: &str) -> String {
+    let (scheme, rest) = match link.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme.to_ascii_lowercase()), rest),
+        None => (None, link),
+    };
+
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (rest, None),
+    };
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None),
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+
+    let (user_info, host_port) = match authority.rsplit_once('@') {
+        Some((user_info, host_port)) => (Some(user_info), host_port),
+        None => (None, authority),
+    };
+    // Brackets of IPv6 hosts contain colons too
+    let (host, port) = match host_port.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => (host, Some(port)),
+        _ => (host_port, None),
+    };
+    let host = host.trim_end_matches('.').to_lowercase();
+    let port = port
+        .filter(|port| !port.is_empty() && scheme.as_deref().and_then(default_port) != Some(*port));
+
+    let mut normalized = String::with_capacity(link.len());
+    if let Some(scheme) = scheme {
+        normalized.push_str(&scheme);
+        normalized.push_str("://");
+    }
+    if let Some(user_info) = user_info {
+        normalized.push_str(user_info);
+        normalized.push('@');
+    }
+    normalized.push_str(&host);
+    if let Some(port) = port {
+        normalized.push(':');
+        normalized.push_str(port);
+    }
+    push_path(&mut normalized, path, query, fragment);
+
+    normalized
+}
+
+/// Pushes the path without a trailing slash, the query without tracking parameters and the
+/// fragment if it routes
+fn push_path(normalized: &mut String, path: &str, query: Option<&str>, fragment: Option<&str>) {
+    normalized.push_str(path.trim_end_matches('/'));
+    let query: Vec<&str> = query
+        .into_iter()
+        .flat_map(|x| x.split('&'))
+        .filter(|x| !x.is_empty() && !is_tracking(x))
+        .collect();
+    if !query.is_empty() {
+        normalized.push('?');
+        normalized.push_str(&query.join("&"));
+    }
+    if let Some(fragment) = fragment.filter(|x| x.starts_with('/') || x.starts_with('!')) {
+        normalized.push('#');
+        normalized.push_str(fragment);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{display, normalize, parse, LinkError};
+
+    #[test]
+    fn drops_what_doesnt_change_the_page() {
+        let same = [
+            "https://www.rust-lang.org",
+            "https://www.rust-lang.org/",
+            "HTTPS://WWW.Rust-Lang.org:443/",
+            "https://www.rust-lang.org/#install",
+            "https://www.rust-lang.org/?utm_source=feed&utm_medium=rss",
+            " https://www.rust-lang.org./?fbclid=abc ",
+        ];
+        for link in same {
+            assert_eq!(normalize(link), "https://www.rust-lang.org", "{link}");
+        }
+
+        assert_eq!(
+            normalize("http://example.com:8080/Docs/?page=2&gclid=x#!/intro"),
+            "http://example.com:8080/Docs?page=2#!/intro"
+        );
+        assert_eq!(
+            normalize("http://example.com:443"),
+            "http://example.com:443"
+        );
+        assert_eq!(normalize("http://[::1]:80/a/"), "http://[::1]/a");
+        assert_eq!(
+            normalize("https://user@Example.com"),
+            "https://user@example.com"
+        );
+        assert_eq!(
+            normalize("https://bücher.de/a/"),
+            normalize("https://xn--bcher-kva.de/a")
+        );
+        // Saved before links were parsed
+        assert_eq!(
+            normalize("WWW.Rust-Lang.org/?utm_source=x"),
+            "www.rust-lang.org"
+        );
+    }
+    #[test]
+    fn parses_typed_links() {
+        let schemes: Vec<String> = ["http", "https", "ftp", "file", "mailto"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let parse = |input: &str| parse(input, &schemes);
+
+        assert_eq!(parse("rust-lang.org").unwrap(), "https://rust-lang.org/");
+        assert_eq!(
+            parse(" localhost:8080/docs ").unwrap(),
+            "https://localhost:8080/docs"
+        );
+        assert_eq!(
+            parse("HTTP://Example.com/a").unwrap(),
+            "http://example.com/a"
+        );
+        assert_eq!(
+            parse("ftp://ftp.example.com/file").unwrap(),
+            "ftp://ftp.example.com/file"
+        );
+        assert_eq!(
+            parse("file:///home/me/notes.txt").unwrap(),
+            "file:///home/me/notes.txt"
+        );
+        assert_eq!(
+            parse("mailto:me@example.com").unwrap(),
+            "mailto:me@example.com"
+        );
+        assert_eq!(
+            parse("https://bücher.de/").unwrap(),
+            "https://xn--bcher-kva.de/"
+        );
+
+        assert_eq!(parse(""), Err(LinkError::Empty));
+        assert_eq!(
+            parse("https://rust-lang.org/a b"),
+            Err(LinkError::Whitespace)
+        );
+        assert_eq!(
+            parse("https://"),
+            Err(LinkError::Url(url::ParseError::EmptyHost))
+        );
+        assert_eq!(parse("rust"), Err(LinkError::NotADomain));
+        assert_eq!(
+            parse("javascript:alert(1)"),
+            Err(LinkError::Scheme("javascript".to_string()))
+        );
+    }
+
+    #[test]
+    fn displays_unicode_hosts() {
+        assert_eq!(display("https://xn--bcher-kva.de/a"), "https://bücher.de/a");
+        assert_eq!(display("https://rust-lang.org/"), "https://rust-lang.org/");
+        assert_eq!(display("not a link"), "not a link");
+    }
+}
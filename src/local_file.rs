@@ -1,11 +1,340 @@
-use serde::Serialize;
+//! Every version of the file format has its own module with its schema. Each one upgrades to the
+//! next, so files written by any older version can be read.
+//!
+//! When [`Bookmark`] changes shape, add a new version module, make the previous one upgrade to it,
+//! bump [`LOCAL_FILE_VERSION`] and add a golden file.
 
-use crate::store::Bookmark;
+use std::str::FromStr;
 
-pub const LOCAL_FILE_VERSION: u64 = 0;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    store::{Bookmark, Collection},
+    time::Timestamp,
+};
+
+pub use errors::*;
+
+mod v0;
+mod v1;
+mod v2;
+mod v3;
+mod v4;
+
+pub const LOCAL_FILE_VERSION: u64 = 4;
+
+type Latest = v4::LocalFile;
+
+mod errors {
+    #[derive(Debug)]
+    pub enum LocalFileError {
+        Json(serde_json::Error),
+        /// File is written by a newer version of the app
+        UnsupportedVersion(u64),
+    }
+
+    impl std::error::Error for LocalFileError {}
+    impl std::fmt::Display for LocalFileError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::Json(err) => write!(f, "Invalid file: {}", err),
+                Self::UnsupportedVersion(version) => {
+                    write!(f, "Unsupported file version: {}", version)
+                }
+            }
+        }
+    }
+    impl From<serde_json::Error> for LocalFileError {
+        fn from(err: serde_json::Error) -> Self {
+            Self::Json(err)
+        }
+    }
+}
 
 #[derive(Serialize)]
 pub struct ToLocalFile<'a> {
     pub version: u64,
     pub bookmarks: Vec<&'a Bookmark>,
+    pub collections: Vec<&'a Collection>,
+}
+
+pub struct FromLocalFile {
+    /// Version the file was written with
+    pub version: u64,
+    pub bookmarks: Vec<Bookmark>,
+    pub collections: Vec<Collection>,
+}
+
+#[derive(Deserialize)]
+struct LocalFileVersion {
+    version: u64,
+}
+
+impl FromStr for FromLocalFile {
+    type Err = LocalFileError;
+
+    fn from_str(file_data: &str) -> Result<Self, Self::Err> {
+        let LocalFileVersion { version } = serde_json::from_str(file_data)?;
+
+        let latest = match version {
+            0 => serde_json::from_str::<v0::LocalFile>(file_data)?.into_latest(),
+            1 => serde_json::from_str::<v1::LocalFile>(file_data)?.into_latest(),
+            2 => serde_json::from_str::<v2::LocalFile>(file_data)?.into_latest(),
+            3 => serde_json::from_str::<v3::LocalFile>(file_data)?.into_latest(),
+            4 => serde_json::from_str::<v4::LocalFile>(file_data)?.into_latest(),
+            version => return Err(LocalFileError::UnsupportedVersion(version)),
+        };
+
+        let bookmarks = latest
+            .bookmarks
+            .into_iter()
+            .map(|x| Bookmark {
+                id: x.id,
+                title: x.title,
+                link: x.link,
+                note: x.note,
+                created_at: x.created_at,
+                updated_at: x.updated_at,
+                last_visited_at: x.last_visited_at,
+                deleted_at: None,
+                collection: x.collection,
+                tags: x.tags,
+            })
+            .collect();
+        let collections = latest
+            .collections
+            .into_iter()
+            .map(|x| Collection {
+                id: x.id,
+                name: x.name,
+                parent: x.parent,
+                created_at: x.created_at,
+                updated_at: x.updated_at,
+            })
+            .collect();
+
+        Ok(Self {
+            version,
+            bookmarks,
+            collections,
+        })
+    }
+}
+
+/// The synced file as it was read or written
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    pub file_data: String,
+    /// `lastModified` of the file
+    pub last_modified: Timestamp,
+}
+
+impl FileSnapshot {
+    /// Whether the file changed since this snapshot was taken. A file that was only touched has
+    /// a new `lastModified` but the same contents, that is not a change.
+    pub fn is_modified(&self, current: &FileSnapshot) -> bool {
+        self.last_modified != current.last_modified && self.file_data != current.file_data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::{FileSnapshot, FromLocalFile, LocalFileError, ToLocalFile, LOCAL_FILE_VERSION};
+    use crate::store::{Bookmark, Collection, CollectionId};
+
+    fn read(file_data: &str) -> FromLocalFile {
+        file_data
+            .parse::<FromLocalFile>()
+            .expect("golden file should be readable")
+    }
+
+    /// Files from before ids get new ones on every read
+    fn assert_eq_without_ids(mut bookmarks: Vec<Bookmark>, expected: Vec<Bookmark>) {
+        for (bookmark, expected) in bookmarks.iter_mut().zip(expected.iter()) {
+            bookmark.id = expected.id;
+        }
+        assert_eq!(bookmarks, expected);
+    }
+
+    const RUST_NOTE: &str =
+        "A language empowering everyone to build reliable and efficient software.";
+
+    #[test]
+    fn v0() {
+        let file = read(include_str!("local_file/golden/v0.json"));
+
+        assert_eq!(file.version, 0);
+        // Builds with tags wrote them before the version got bumped to 1
+        assert_eq_without_ids(
+            file.bookmarks,
+            vec![
+                Bookmark::new("Rust", "https://www.rust-lang.org/", RUST_NOTE, 0)
+                    .with_tags(&["rust", "language"]),
+                Bookmark::new("Dioxus", "https://dioxuslabs.com", "", 0),
+            ],
+        );
+    }
+
+    #[test]
+    fn v1() {
+        let file = read(include_str!("local_file/golden/v1.json"));
+
+        assert_eq!(file.version, 1);
+        assert_eq_without_ids(
+            file.bookmarks,
+            vec![
+                Bookmark::new("Rust", "https://www.rust-lang.org/", RUST_NOTE, 0)
+                    .with_tags(&["rust", "language"]),
+                Bookmark::new("Dioxus", "https://dioxuslabs.com", "", 0),
+            ],
+        );
+    }
+
+    #[test]
+    fn v2() {
+        let file = read(include_str!("local_file/golden/v2.json"));
+
+        assert_eq!(file.version, 2);
+        assert_eq!(
+            file.bookmarks
+                .iter()
+                .map(|x| x.id.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "0f3a4b6c-5d2e-4f10-9a8b-7c6d5e4f3a2b",
+                "6a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d",
+            ]
+        );
+        assert_eq_without_ids(
+            file.bookmarks,
+            vec![
+                Bookmark::new("Rust", "https://www.rust-lang.org/", RUST_NOTE, 0)
+                    .with_tags(&["rust", "language"]),
+                Bookmark::new("Dioxus", "https://dioxuslabs.com", "", 0),
+            ],
+        );
+    }
+
+    #[test]
+    fn v3() {
+        let file = read(include_str!("local_file/golden/v3.json"));
+
+        assert_eq!(file.version, 3);
+
+        let mut rust = Bookmark::new("Rust", "https://www.rust-lang.org/", RUST_NOTE, 0)
+            .with_tags(&["rust", "language"]);
+        rust.created_at = 1717243200000;
+        rust.updated_at = 1717329600000;
+        rust.last_visited_at = Some(1717416000000);
+
+        let mut dioxus = Bookmark::new("Dioxus", "https://dioxuslabs.com", "", 0);
+        dioxus.created_at = 1717250400000;
+        dioxus.updated_at = 1717250400000;
+
+        assert_eq_without_ids(file.bookmarks, vec![rust, dioxus]);
+    }
+
+    #[test]
+    fn v4() {
+        let file = read(include_str!("local_file/golden/v4.json"));
+
+        assert_eq!(file.version, 4);
+
+        let collection_id =
+            |id: &str| serde_json::from_str::<CollectionId>(&format!("\"{id}\"")).unwrap();
+        let languages = Collection {
+            id: collection_id("3b5d7f9a-2c4e-4b6d-8f0a-1c3e5a7b9d2f"),
+            name: Rc::from("Languages"),
+            parent: None,
+            created_at: 1717243200000,
+            updated_at: 1717243200000,
+        };
+        let frameworks = Collection {
+            id: collection_id("8c0e2a4b-6d8f-4c1e-9a3b-5d7f9b1c3e5a"),
+            name: Rc::from("Frameworks"),
+            parent: Some(languages.id),
+            created_at: 1717250400000,
+            updated_at: 1717250400000,
+        };
+
+        let mut rust = Bookmark::new("Rust", "https://www.rust-lang.org/", RUST_NOTE, 0)
+            .with_tags(&["rust", "language"]);
+        rust.created_at = 1717243200000;
+        rust.updated_at = 1717329600000;
+        rust.last_visited_at = Some(1717416000000);
+        rust.collection = Some(languages.id);
+
+        let mut dioxus = Bookmark::new("Dioxus", "https://dioxuslabs.com", "", 0);
+        dioxus.created_at = 1717250400000;
+        dioxus.updated_at = 1717250400000;
+        dioxus.collection = Some(frameworks.id);
+
+        assert_eq_without_ids(file.bookmarks, vec![rust, dioxus]);
+        assert_eq!(file.collections, vec![languages, frameworks]);
+    }
+
+    #[test]
+    fn latest_round_trips() {
+        let mut bookmarks = vec![
+            Bookmark::new("Rust", "https://www.rust-lang.org/", RUST_NOTE, 0).with_tags(&["rust"]),
+            Bookmark::new("Dioxus", "https://dioxuslabs.com", "", 0),
+        ];
+        bookmarks[0].created_at = 1;
+        bookmarks[0].updated_at = 2;
+        bookmarks[0].last_visited_at = Some(3);
+        let collections = vec![Collection {
+            id: CollectionId::new(),
+            name: Rc::from("Languages"),
+            parent: None,
+            created_at: 1,
+            updated_at: 1,
+        }];
+        bookmarks[0].collection = Some(collections[0].id);
+        let to_local_file = ToLocalFile {
+            version: LOCAL_FILE_VERSION,
+            bookmarks: bookmarks.iter().collect(),
+            collections: collections.iter().collect(),
+        };
+        let file_data = serde_json::to_string_pretty(&to_local_file).unwrap();
+
+        let file = read(&file_data);
+        assert_eq!(file.version, LOCAL_FILE_VERSION);
+        assert_eq!(file.bookmarks, bookmarks);
+        assert_eq!(file.collections, collections);
+    }
+
+    #[test]
+    fn newer_version_is_unsupported() {
+        let file_data = format!(
+            r#"{{ "version": {}, "bookmarks": [] }}"#,
+            LOCAL_FILE_VERSION + 1
+        );
+        let result = file_data.parse::<FromLocalFile>();
+
+        assert!(matches!(
+            result,
+            Err(LocalFileError::UnsupportedVersion(version)) if version == LOCAL_FILE_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn missing_version_is_invalid() {
+        let result = r#"{ "bookmarks": [] }"#.parse::<FromLocalFile>();
+        assert!(matches!(result, Err(LocalFileError::Json(_))));
+    }
+
+    #[test]
+    fn snapshot_detects_modification() {
+        let snapshot = |file_data: &str, last_modified| FileSnapshot {
+            file_data: file_data.to_string(),
+            last_modified,
+        };
+        let written = snapshot("{}", 1);
+
+        assert!(!written.is_modified(&snapshot("{}", 1)));
+        assert!(!written.is_modified(&snapshot("{}", 2)));
+        assert!(written.is_modified(&snapshot("{ }", 2)));
+    }
 }
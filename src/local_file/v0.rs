@@ -0,0 +1,42 @@
+//! Initial format, bookmarks only have title, link and note.
+
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use super::v1;
+
+#[derive(Deserialize)]
+pub struct LocalFile {
+    pub bookmarks: Vec<Bookmark>,
+}
+
+#[derive(Deserialize)]
+pub struct Bookmark {
+    pub title: Rc<str>,
+    pub link: Rc<str>,
+    pub note: Rc<str>,
+    /// Tags were written before the version got bumped to 1
+    #[serde(default)]
+    pub tags: Vec<Rc<str>>,
+}
+
+impl LocalFile {
+    pub fn upgrade(self) -> v1::LocalFile {
+        let bookmarks = self
+            .bookmarks
+            .into_iter()
+            .map(|x| v1::Bookmark {
+                title: x.title,
+                link: x.link,
+                note: x.note,
+                tags: x.tags,
+            })
+            .collect();
+
+        v1::LocalFile { bookmarks }
+    }
+    pub fn into_latest(self) -> super::Latest {
+        self.upgrade().into_latest()
+    }
+}
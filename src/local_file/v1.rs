@@ -0,0 +1,43 @@
+//! Adds tags.
+
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use super::v2;
+use crate::store::BookmarkId;
+
+#[derive(Deserialize)]
+pub struct LocalFile {
+    pub bookmarks: Vec<Bookmark>,
+}
+
+#[derive(Deserialize)]
+pub struct Bookmark {
+    pub title: Rc<str>,
+    pub link: Rc<str>,
+    pub note: Rc<str>,
+    pub tags: Vec<Rc<str>>,
+}
+
+impl LocalFile {
+    /// Every bookmark gets a new id
+    pub fn upgrade(self) -> v2::LocalFile {
+        let bookmarks = self
+            .bookmarks
+            .into_iter()
+            .map(|x| v2::Bookmark {
+                id: BookmarkId::new(),
+                title: x.title,
+                link: x.link,
+                note: x.note,
+                tags: x.tags,
+            })
+            .collect();
+
+        v2::LocalFile { bookmarks }
+    }
+    pub fn into_latest(self) -> super::Latest {
+        self.upgrade().into_latest()
+    }
+}
@@ -0,0 +1,47 @@
+//! Adds ids, so a bookmark is recognized when the file is read back.
+
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use super::v3;
+use crate::store::BookmarkId;
+
+#[derive(Deserialize)]
+pub struct LocalFile {
+    pub bookmarks: Vec<Bookmark>,
+}
+
+#[derive(Deserialize)]
+pub struct Bookmark {
+    pub id: BookmarkId,
+    pub title: Rc<str>,
+    pub link: Rc<str>,
+    pub note: Rc<str>,
+    pub tags: Vec<Rc<str>>,
+}
+
+impl LocalFile {
+    /// Timestamps weren't kept, they are left at 0
+    pub fn upgrade(self) -> v3::LocalFile {
+        let bookmarks = self
+            .bookmarks
+            .into_iter()
+            .map(|x| v3::Bookmark {
+                id: x.id,
+                title: x.title,
+                link: x.link,
+                note: x.note,
+                created_at: 0,
+                updated_at: 0,
+                last_visited_at: None,
+                tags: x.tags,
+            })
+            .collect();
+
+        v3::LocalFile { bookmarks }
+    }
+    pub fn into_latest(self) -> super::Latest {
+        self.upgrade().into_latest()
+    }
+}
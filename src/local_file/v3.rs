@@ -0,0 +1,55 @@
+//! Adds created, updated and last visited timestamps.
+
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use super::v4;
+use crate::{store::BookmarkId, time::Timestamp};
+
+#[derive(Deserialize)]
+pub struct LocalFile {
+    pub bookmarks: Vec<Bookmark>,
+}
+
+#[derive(Deserialize)]
+pub struct Bookmark {
+    pub id: BookmarkId,
+    pub title: Rc<str>,
+    pub link: Rc<str>,
+    pub note: Rc<str>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    #[serde(default)]
+    pub last_visited_at: Option<Timestamp>,
+    pub tags: Vec<Rc<str>>,
+}
+
+impl LocalFile {
+    /// Collections didn't exist, every bookmark is at the top level
+    pub fn upgrade(self) -> v4::LocalFile {
+        let bookmarks = self
+            .bookmarks
+            .into_iter()
+            .map(|x| v4::Bookmark {
+                id: x.id,
+                title: x.title,
+                link: x.link,
+                note: x.note,
+                created_at: x.created_at,
+                updated_at: x.updated_at,
+                last_visited_at: x.last_visited_at,
+                collection: None,
+                tags: x.tags,
+            })
+            .collect();
+
+        v4::LocalFile {
+            bookmarks,
+            collections: Vec::new(),
+        }
+    }
+    pub fn into_latest(self) -> super::Latest {
+        self.upgrade().into_latest()
+    }
+}
@@ -0,0 +1,47 @@
+//! Adds collections, and the collection each bookmark is in.
+
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use crate::{
+    store::{BookmarkId, CollectionId},
+    time::Timestamp,
+};
+
+#[derive(Deserialize)]
+pub struct LocalFile {
+    pub bookmarks: Vec<Bookmark>,
+    pub collections: Vec<Collection>,
+}
+
+#[derive(Deserialize)]
+pub struct Bookmark {
+    pub id: BookmarkId,
+    pub title: Rc<str>,
+    pub link: Rc<str>,
+    pub note: Rc<str>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    #[serde(default)]
+    pub last_visited_at: Option<Timestamp>,
+    #[serde(default)]
+    pub collection: Option<CollectionId>,
+    pub tags: Vec<Rc<str>>,
+}
+
+#[derive(Deserialize)]
+pub struct Collection {
+    pub id: CollectionId,
+    pub name: Rc<str>,
+    #[serde(default)]
+    pub parent: Option<CollectionId>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+impl LocalFile {
+    pub fn into_latest(self) -> super::Latest {
+        self
+    }
+}
@@ -1,47 +1,456 @@
 #![allow(non_snake_case)]
 
 mod arena;
+mod autosave;
 mod bit_field;
 mod button;
+mod collection_tree;
+mod conflict_dialog;
+mod database;
+mod diff;
+mod duplicates_dialog;
+mod error;
+mod export;
+mod export_menu;
 mod file_system;
+mod history_panel;
+mod import;
+mod import_preview;
+mod library;
+mod library_switcher;
+mod link;
 mod local_file;
+mod merge;
+mod netscape;
+mod search;
+mod settings;
+mod shortcuts;
+mod sidebar;
+mod storage;
 mod store;
+mod time;
+mod toast;
+mod trash;
 mod world;
 
 use arena::ArenaId;
+use autosave::{AutosaveStatus, SaveStatus, AUTOSAVE_DELAY};
 use button::{Button, ButtonSize};
+use collection_tree::{CollectionTree, Dragged};
+use conflict_dialog::{ConflictDialog, PendingMerge};
 use dioxus::prelude::*;
-use file_system::save_to_file;
-use futures_util::StreamExt;
-use idb::{DatabaseEvent, Factory, ObjectStoreParams, TransactionMode};
-use local_file::{ToLocalFile, LOCAL_FILE_VERSION};
-use serde::Serialize;
-use serde_wasm_bindgen::Serializer;
-use store::{Bookmark, Store};
+use duplicates_dialog::{duplicate_groups, DuplicateGroups, DuplicatesDialog};
+use error::AppError;
+use export::{Exporter, LocalFileExporter};
+use export_menu::ExportMenu;
+use file_system::{open_from_file, read_from_handle, save_to_file, verify_permission};
+use futures_util::{
+    future::{select, Either},
+    StreamExt,
+};
+use history_panel::{BookmarkHistory, HistoryPanel};
+use import::ImportError;
+use import_preview::{ImportPreview, PendingImport};
+use library::{Libraries, Library, LibraryId};
+use library_switcher::LibrarySwitcher;
+use local_file::{FileSnapshot, FromLocalFile, LOCAL_FILE_VERSION};
+use merge::Merge;
+use settings::Settings;
+use shortcuts::use_undo_shortcuts;
+use sidebar::TagList;
+use std::{pin::pin, rc::Rc};
+use storage::{MemoryStorage, StorageBackend, Stored, Write};
+use store::{
+    Bookmark, BookmarkId, Collection, CollectionId, Command, HistoryEntry, SortOrder, Store,
+    Transfer, UndoStack,
+};
+use toast::{notify, Toast, Toasts};
 use tracing::Level;
-use wasm_bindgen::{JsCast, JsValue};
+use trash::Trash;
 use web_sys::FileSystemFileHandle;
 
-
 fn main() {
     // Init logger
     dioxus_logger::init(Level::INFO).expect("failed to init logger");
     launch(App);
 }
 
+#[derive(Clone)]
 enum Action {
     CreateBookmark {
         title: String,
         link: String,
         note: String,
+        tags: Vec<String>,
+    },
+    UpdateBookmark {
+        id: ArenaId<Bookmark>,
+        title: String,
+        link: String,
+        note: String,
+    },
+    DeleteBookmark {
+        id: ArenaId<Bookmark>,
+    },
+    RestoreBookmark {
+        id: ArenaId<Bookmark>,
+    },
+    VisitBookmark {
+        id: ArenaId<Bookmark>,
+    },
+    SetTrashRetention {
+        days: u32,
+    },
+    TagBookmark {
+        id: ArenaId<Bookmark>,
+        tag: String,
+    },
+    UntagBookmark {
+        id: ArenaId<Bookmark>,
+        tag: String,
     },
+    Search {
+        query: String,
+    },
+    QueryTags {
+        query: String,
+    },
+    SetSort {
+        sort: SortOrder,
+    },
+    /// Shows only bookmarks in the collection and the ones inside it, all of them if None
+    SelectCollection {
+        id: Option<ArenaId<Collection>>,
+    },
+    CreateCollection {
+        name: String,
+        parent: Option<ArenaId<Collection>>,
+    },
+    DeleteCollection {
+        id: ArenaId<Collection>,
+    },
+    MoveCollection {
+        id: ArenaId<Collection>,
+        parent: Option<ArenaId<Collection>>,
+    },
+    /// Moves the bookmark into the collection, or to the top level if None
+    FileBookmark {
+        id: ArenaId<Bookmark>,
+        collection: Option<ArenaId<Collection>>,
+    },
+    /// Reads a file to preview, see [`ImportPreview`]
+    Import,
+    /// Imports the previewed bookmarks
+    ConfirmImport,
     Sync,
+    SetAutosave {
+        enabled: bool,
+    },
+    SetAllowedSchemes {
+        schemes: Vec<String>,
+    },
+    /// Writes to the synced file, `request` asks for permission again if it lapsed
+    Autosave {
+        request: bool,
+    },
+    /// Applies the sides picked in [`ConflictDialog`] and syncs
+    ResolveConflicts,
+    /// Creates a library and switches to it
+    CreateLibrary {
+        name: String,
+    },
+    SwitchLibrary {
+        id: LibraryId,
+    },
+    /// Puts a copy with a new id in the other library
+    CopyToLibrary {
+        id: ArenaId<Bookmark>,
+        library: LibraryId,
+    },
+    /// Puts the bookmark and its history in the other library and removes them from this one,
+    /// without trash. Undo takes them back, see [`Command::MoveBookmark`].
+    MoveToLibrary {
+        id: ArenaId<Bookmark>,
+        library: LibraryId,
+    },
+    /// Asks where to save and writes the library with the exporter
+    Export {
+        exporter: &'static dyn Exporter,
+    },
+    /// Reverts the last edit, see [`UndoStack`]
+    Undo,
+    Redo,
+    /// Loads the bookmark's edits into [`HistoryPanel`]
+    ShowHistory {
+        id: ArenaId<Bookmark>,
+    },
+    /// Opens [`DuplicatesDialog`]
+    FindDuplicates,
+    /// Merges each group into its oldest bookmark, as one undo step
+    MergeDuplicates {
+        groups: Vec<Vec<ArenaId<Bookmark>>>,
+    },
+    /// Sets title, link and note back to the ones kept in the entry
+    RestoreVersion {
+        id: ArenaId<Bookmark>,
+        entry: HistoryEntry,
+    },
+    /// Writes pending changes to IndexedDB
+    Persist,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum View {
+    Bookmarks,
+    Trash,
 }
 
 struct CreateBookmark {
     title: String,
     link: String,
     note: String,
+    tags: Vec<String>,
+}
+
+struct UpdateBookmark {
+    id: ArenaId<Bookmark>,
+    title: String,
+    link: String,
+    note: String,
+}
+
+/// Comma separated tags, see [`store::normalize_tag`]
+fn parse_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(store::normalize_tag)
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+/// Comma separated, lowercase and without `:`, see [`Settings::allowed_schemes`]
+fn parse_schemes(schemes: &str) -> Vec<String> {
+    let mut parsed: Vec<String> = Vec::new();
+    for scheme in schemes.split(',') {
+        let scheme = scheme.trim().trim_end_matches(':').to_ascii_lowercase();
+        if !scheme.is_empty() && !parsed.contains(&scheme) {
+            parsed.push(scheme);
+        }
+    }
+    parsed
+}
+
+/// Any format one of [`import::IMPORTERS`] recognizes
+async fn import_file(schemes: &[String]) -> Result<PendingImport, AppError> {
+    let file_data = open_from_file().await?;
+
+    let importer = import::detect(&file_data).ok_or(ImportError::UnknownFormat)?;
+    let file = importer.import(&file_data, schemes)?;
+
+    Ok(PendingImport {
+        format: importer.name(),
+        bookmarks: file.bookmarks,
+        collections: file.collections,
+    })
+}
+
+enum Synced {
+    Written,
+    /// Writing needs permission, see [`verify_permission`]
+    NeedsPermission,
+    /// The file was changed elsewhere since it was last written, nothing was written. The merge
+    /// is applied to the library, then the library is written over the file as it is now.
+    Diverged(Merge, FileSnapshot),
+}
+
+/// Asks for a file if there is no handle yet and remembers it. Changes made to the file since
+/// `sync_base` are merged instead of being overwritten.
+async fn sync_file(
+    backend: &dyn StorageBackend,
+    store: Signal<Store>,
+    handle: &mut Option<FileSystemFileHandle>,
+    sync_base: &mut Option<FileSnapshot>,
+    request: bool,
+) -> Result<Synced, AppError> {
+    if let Some(handle) = handle.as_ref() {
+        if !verify_permission(handle, request).await? {
+            return Ok(Synced::NeedsPermission);
+        }
+
+        let current = read_from_handle(handle).await?;
+        // A file sync hasn't written yet is merged too, unless it is empty
+        let is_modified = match sync_base.as_ref() {
+            Some(sync_base) => sync_base.is_modified(&current),
+            None => !current.file_data.trim().is_empty(),
+        };
+
+        if is_modified {
+            let base = match sync_base.as_ref() {
+                Some(sync_base) => sync_base.file_data.parse::<FromLocalFile>()?,
+                None => FromLocalFile {
+                    version: LOCAL_FILE_VERSION,
+                    bookmarks: Vec::new(),
+                    collections: Vec::new(),
+                },
+            };
+            let theirs = current.file_data.parse::<FromLocalFile>()?;
+            let (ours, our_collections) = {
+                let store_ref = store.peek();
+                let ours = store_ref.all_data().cloned().collect::<Vec<_>>();
+                let our_collections = store_ref.all_collections().cloned().collect::<Vec<_>>();
+                (ours, our_collections)
+            };
+
+            let mut merge = merge::merge(
+                &base.bookmarks,
+                &ours.iter().collect::<Vec<_>>(),
+                &theirs.bookmarks,
+            );
+            merge::merge_collections(
+                &mut merge,
+                &base.collections,
+                &our_collections.iter().collect::<Vec<_>>(),
+                &theirs.collections,
+            );
+
+            if !merge.is_empty() {
+                return Ok(Synced::Diverged(merge, current));
+            }
+        }
+    }
+
+    let file_data = {
+        let store_ref = store.peek();
+        LocalFileExporter.export(
+            &store_ref.all_data().collect::<Vec<_>>(),
+            &store_ref.all_collections().collect::<Vec<_>>(),
+        )?
+    };
+    let file_name = LocalFileExporter.file_name();
+    let returned_handle = save_to_file(handle.clone(), file_data, file_name).await?;
+    let written = read_from_handle(&returned_handle).await?;
+
+    let mut writes = vec![Write::PutSyncBase(written.clone())];
+    if handle.is_none() {
+        *handle = Some(returned_handle.clone());
+        writes.push(Write::PutHandle(returned_handle));
+    }
+    backend.transaction(writes).await?;
+    *sync_base = Some(written);
+
+    Ok(Synced::Written)
+}
+
+/// Storage of a library that may not be open, the default one's is always open
+async fn open_target(
+    registry: &Rc<dyn StorageBackend>,
+    library: LibraryId,
+) -> Result<Rc<dyn StorageBackend>, AppError> {
+    match library {
+        LibraryId::DEFAULT => Ok(registry.clone()),
+        library => Ok(storage::open(library).await?.into()),
+    }
+}
+
+/// Moves the bookmark's history along with it, see [`Transfer`]
+async fn transfer_bookmark(
+    backend: &dyn StorageBackend,
+    registry: &Rc<dyn StorageBackend>,
+    transfer: &Transfer,
+) -> Result<(), AppError> {
+    match transfer {
+        Transfer::Out(bookmark, library) => {
+            let mut bookmark = bookmark.clone();
+            // Collections belong to this library
+            bookmark.collection = None;
+
+            let history = backend.history(bookmark.id).await?;
+            let mut writes = vec![Write::PutBookmark(bookmark)];
+            writes.extend(history.into_iter().map(Write::AddHistory));
+            open_target(registry, *library)
+                .await?
+                .transaction(writes)
+                .await?;
+        }
+        Transfer::In(id, library) => {
+            let target = open_target(registry, *library).await?;
+            let history = backend.history(*id).await?;
+            // Already taken back if removing it there failed before
+            if history.is_empty() {
+                let writes = target.history(*id).await?;
+                backend
+                    .transaction(writes.into_iter().map(Write::AddHistory).collect())
+                    .await?;
+            }
+            target
+                .transaction(vec![Write::DeleteBookmark(*id), Write::DeleteHistory(*id)])
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn open_library(library: LibraryId) -> Result<(Rc<dyn StorageBackend>, Stored), AppError> {
+    let backend: Rc<dyn StorageBackend> = storage::open(library).await?.into();
+    let stored = backend.load().await?;
+
+    Ok((backend, stored))
+}
+
+async fn load_history(
+    backend: &dyn StorageBackend,
+    store: Signal<Store>,
+    id: ArenaId<Bookmark>,
+) -> Result<Option<BookmarkHistory>, AppError> {
+    let Some(bookmark) = store.peek().try_bookmark(id).cloned() else {
+        return Ok(None);
+    };
+    let entries = backend.history(bookmark.id).await?;
+
+    Ok(Some(BookmarkHistory {
+        id,
+        bookmark,
+        entries,
+    }))
+}
+
+async fn persist(
+    backend: &dyn StorageBackend,
+    store: Signal<Store>,
+    changes: &[ArenaId<Bookmark>],
+    removals: &[BookmarkId],
+    collection_changes: &[ArenaId<Collection>],
+    collection_removals: &[CollectionId],
+    history: &[HistoryEntry],
+) -> Result<(), AppError> {
+    let writes = {
+        let store_ref = store.peek();
+        let puts = changes
+            .iter()
+            .map(|id| Write::PutBookmark(store_ref.bookmark(*id).clone()));
+        // History goes with the bookmark
+        let deletes = removals
+            .iter()
+            .flat_map(|id| [Write::DeleteBookmark(*id), Write::DeleteHistory(*id)]);
+        let collection_puts = collection_changes
+            .iter()
+            .map(|id| Write::PutCollection(store_ref.collection(*id).clone()));
+        let collection_deletes = collection_removals
+            .iter()
+            .map(|id| Write::DeleteCollection(*id));
+        let history_adds = history.iter().map(|x| Write::AddHistory(x.clone()));
+        puts.chain(deletes)
+            .chain(collection_puts)
+            .chain(collection_deletes)
+            .chain(history_adds)
+            .collect::<Vec<_>>()
+    };
+
+    if !writes.is_empty() {
+        backend.transaction(writes).await?;
+    }
+
+    Ok(())
 }
 
 #[component]
@@ -50,204 +459,818 @@ fn App() -> Element {
     let mut drawer_title = use_signal(|| String::new());
     let mut drawer_link = use_signal(|| String::new());
     let mut drawer_note = use_signal(|| String::new());
+    let mut drawer_tags = use_signal(|| String::new());
+    let mut tag_query = use_signal(|| String::new());
+    let mut view = use_signal(|| View::Bookmarks);
 
     // Don't ever read this. Only write in coroutine. Drop write access before any .await.
-    // It is safe to peek it everywhere.
+    // It is safe to peek it everywhere. Read `store_version` to peek again after the store changes.
     let mut store = use_signal(move || Store::new());
+    let mut store_version = use_signal(|| 0_u64);
     let mut cards = use_signal(move || Vec::with_capacity(0));
+    let mut trash_cards = use_signal(move || Vec::with_capacity(0));
+    let mut tags = use_signal(move || Vec::with_capacity(0));
+    let mut collections = use_signal(move || Vec::with_capacity(0));
+    let mut selected_collection = use_signal(|| None::<ArenaId<Collection>>);
+    let dragged = use_signal(|| None::<Dragged>);
+    let mut can_undo = use_signal(|| false);
+    let mut can_redo = use_signal(|| false);
+    let mut tag_query_error = use_signal(|| None::<String>);
+    let mut import_status = use_signal(|| None::<String>);
+    let mut import_preview = use_signal(|| None::<PendingImport>);
+    let mut bookmark_history = use_signal(|| None::<BookmarkHistory>);
+    let mut duplicates = use_signal(|| None::<DuplicateGroups>);
+    let mut settings = use_signal(Settings::default);
+    let mut save_status = use_signal(|| SaveStatus::Idle);
+    let mut pending_merge = use_signal(|| None::<PendingMerge>);
+    let mut libraries = use_signal(Libraries::default);
+    let toasts = use_signal(Vec::<Toast>::new);
 
     let coroutine = use_coroutine(move |mut rx: UnboundedReceiver<Action>| async move {
-        let serializer = Serializer::json_compatible();
-
-        // Handle all the errors
-        let factory = Factory::new().expect("Should be able to get factory");
-        let mut indexed_db = factory
-            .open("librarian", Some(1))
-            .expect("should be able to open DB");
-
-        indexed_db.on_upgrade_needed(|ev| {
-            let database = ev.database().expect("DB should exist");
-
-            let store_params = ObjectStoreParams::new();
+        // Default library's storage, it also keeps the list of libraries
+        let registry: Rc<dyn StorageBackend> = match storage::open(LibraryId::DEFAULT).await {
+            Ok(backend) => backend.into(),
+            Err(err) => {
+                let message = format!("Couldn't open the database, changes won't be saved. {err}");
+                notify(toasts, message, None);
+                Rc::new(MemoryStorage::default())
+            }
+        };
 
-            database
-                .create_object_store("bookmarks", store_params)
-                .expect("should be able to create store");
+        let mut stored = match registry.load().await {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                let message = format!("Couldn't load bookmarks, changes won't be saved. {err}");
+                notify(toasts, message, None);
+                return;
+            }
+        };
+        let mut local_libraries = std::mem::take(&mut stored.libraries);
 
-            let store_params = ObjectStoreParams::new();
+        let (mut backend, stored) = match local_libraries.current {
+            LibraryId::DEFAULT => (registry.clone(), stored),
+            current => match open_library(current).await {
+                Ok(opened) => opened,
+                Err(err) => {
+                    let message = format!("Couldn't open the library. {err}");
+                    notify(toasts, message, None);
 
-            database
-                .create_object_store("handles", store_params)
-                .expect("should be able to create store");
-        });
+                    local_libraries.current = LibraryId::DEFAULT;
+                    (registry.clone(), stored)
+                }
+            },
+        };
+        libraries.set(local_libraries.clone());
 
-        let indexed_db = indexed_db.await.expect("should be able to open DB");
-
-        let transaction = indexed_db
-            .transaction(&["bookmarks", "handles"], TransactionMode::ReadOnly)
-            .expect("should be able to create transaction");
-        let bookmarks_os = transaction
-            .object_store("bookmarks")
-            .expect("should be able to access object store");
-
-        let entries = bookmarks_os
-            .get_all(None, None)
-            .expect("should be able to get all entries")
-            .await
-            .expect("should be able to get all entries");
-
-        let handles_os = transaction
-            .object_store("handles")
-            .expect("should be able to access object store");
-
-        let initial_handle_name = JsValue::from_str("initial_file");
-        let mut handle = handles_os
-            .get(initial_handle_name.clone())
-            .expect("should be able to access initial file")
-            .await
-            .expect("should be able to get initial file")
-            .and_then(|x| x.dyn_into::<FileSystemFileHandle>().ok());
+        let Stored {
+            bookmarks,
+            collections: stored_collections,
+            mut handle,
+            mut sync_base,
+            settings: mut local_settings,
+            ..
+        } = stored;
 
         {
             let mut store_mut = store.write();
 
-            for entry in entries {
-                let bookmark: Bookmark =
-                    serde_wasm_bindgen::from_value(entry).expect("should be able to deserialize");
+            for collection in stored_collections {
+                store_mut.add_collection(collection);
+            }
+            for bookmark in bookmarks {
                 store_mut.add_bookmark(bookmark);
             }
-
-            *cards.write() = store_mut.all_ids().collect();
         }
 
-        transaction
-            .await
-            .expect("transaction should be able to complete");
-
-        drop(bookmarks_os);
-        drop(handles_os);
+        settings.set(local_settings.clone());
 
         let mut created_bookmarks: Vec<CreateBookmark> = Vec::with_capacity(128);
+        let mut updated_bookmarks: Vec<UpdateBookmark> = Vec::with_capacity(128);
+        let mut deleted_bookmarks: Vec<ArenaId<Bookmark>> = Vec::with_capacity(128);
+        let mut restored_bookmarks: Vec<ArenaId<Bookmark>> = Vec::with_capacity(128);
+        let mut visited_bookmarks: Vec<ArenaId<Bookmark>> = Vec::with_capacity(128);
+        let mut tagged_bookmarks: Vec<(ArenaId<Bookmark>, String)> = Vec::with_capacity(128);
+        let mut untagged_bookmarks: Vec<(ArenaId<Bookmark>, String)> = Vec::with_capacity(128);
+        let mut imported_bookmarks: Option<PendingImport> = None;
+        let mut merged_bookmarks: Vec<Bookmark> = Vec::new();
+        let mut merged_removals: Vec<BookmarkId> = Vec::new();
+        let mut moved_bookmarks: Vec<(ArenaId<Bookmark>, LibraryId)> = Vec::new();
+        let mut filed_bookmarks: Vec<(ArenaId<Bookmark>, Option<ArenaId<Collection>>)> = Vec::new();
+        let mut restored_versions: Vec<(ArenaId<Bookmark>, HistoryEntry)> = Vec::new();
+        let mut merged_duplicates: Vec<Vec<ArenaId<Bookmark>>> = Vec::new();
+        let mut created_collections: Vec<(String, Option<ArenaId<Collection>>)> = Vec::new();
+        let mut deleted_collections: Vec<ArenaId<Collection>> = Vec::new();
+        let mut moved_collections: Vec<(ArenaId<Collection>, Option<ArenaId<Collection>>)> =
+            Vec::new();
+        let mut merged_collections: Vec<Collection> = Vec::new();
+        let mut merged_collection_removals: Vec<CollectionId> = Vec::new();
+        let mut search_query = String::new();
+        let mut tag_filter = String::new();
+        let mut sort_order = SortOrder::default();
+        let mut collection_filter: Option<ArenaId<Collection>> = None;
+        // Only edits made in this library, it starts empty when switching
+        let mut undo_stack = UndoStack::default();
+        let (mut undo, mut redo) = (false, false);
+        // Changes that are not in the synced file yet
+        let mut unsaved = false;
+        // Runs before waiting for the next action, once this one's changes are applied
+        let mut queued: Option<Action> = None;
+
+        // First iteration runs without an action, so the initial state gets purged and synced
+        let mut action = None;
+
+        loop {
+            match action.take() {
+                None | Some(Action::Persist) => {}
+                Some(Action::DeleteBookmark { id }) => {
+                    deleted_bookmarks.push(id);
+                }
+                Some(Action::RestoreBookmark { id }) => {
+                    restored_bookmarks.push(id);
+                }
+                Some(Action::VisitBookmark { id }) => {
+                    visited_bookmarks.push(id);
+                }
+                Some(Action::TagBookmark { id, tag }) => {
+                    tagged_bookmarks.push((id, tag));
+                }
+                Some(Action::UntagBookmark { id, tag }) => {
+                    untagged_bookmarks.push((id, tag));
+                }
+                Some(Action::Search { query }) => {
+                    search_query = query;
+                }
+                Some(Action::QueryTags { query }) => {
+                    tag_filter = query;
+                }
+                Some(Action::SetSort { sort }) => {
+                    sort_order = sort;
+                }
+                Some(Action::Undo) => {
+                    undo = true;
+                }
+                Some(Action::Redo) => {
+                    redo = true;
+                }
+                Some(Action::ShowHistory { id }) => {
+                    match load_history(&*backend, store, id).await {
+                        Ok(loaded) => bookmark_history.set(loaded),
+                        Err(err) => {
+                            let message = format!("Couldn't load history. {err}");
+                            notify(toasts, message, Some(Action::ShowHistory { id }));
+                        }
+                    }
+                }
+                Some(Action::FindDuplicates) => {
+                    // Filled when syncing to UI, like after every action while it is open
+                    duplicates.set(Some(Vec::new()));
+                }
+                Some(Action::MergeDuplicates { groups }) => {
+                    merged_duplicates.extend(groups);
+                }
+                Some(Action::RestoreVersion { id, entry }) => {
+                    restored_versions.push((id, entry));
+                }
+                Some(Action::SelectCollection { id }) => {
+                    collection_filter = id;
+                }
+                Some(Action::CreateCollection { name, parent }) => {
+                    created_collections.push((name, parent));
+                }
+                Some(Action::DeleteCollection { id }) => {
+                    deleted_collections.push(id);
+                }
+                Some(Action::MoveCollection { id, parent }) => {
+                    moved_collections.push((id, parent));
+                }
+                Some(Action::FileBookmark { id, collection }) => {
+                    filed_bookmarks.push((id, collection));
+                }
+                Some(Action::SetTrashRetention { days }) => {
+                    local_settings.trash_retention_days = days;
+                    settings.set(local_settings.clone());
 
-        while let Some(action) = rx.next().await {
-            match action {
-                Action::CreateBookmark { title, link, note } => {
+                    if let Err(err) = backend.put_settings(local_settings.clone()).await {
+                        let message = format!("Couldn't save settings. {err}");
+                        notify(toasts, message, Some(Action::SetTrashRetention { days }));
+                    }
+                }
+                Some(Action::SetAutosave { enabled }) => {
+                    local_settings.autosave = enabled;
+                    settings.set(local_settings.clone());
+
+                    if enabled && handle.is_none() {
+                        save_status.set(SaveStatus::NoFile);
+                    }
+
+                    if let Err(err) = backend.put_settings(local_settings.clone()).await {
+                        let message = format!("Couldn't save settings. {err}");
+                        notify(toasts, message, Some(Action::SetAutosave { enabled }));
+                    }
+                }
+                Some(Action::SetAllowedSchemes { schemes }) => {
+                    local_settings.allowed_schemes = schemes.clone();
+                    settings.set(local_settings.clone());
+
+                    if let Err(err) = backend.put_settings(local_settings.clone()).await {
+                        let message = format!("Couldn't save settings. {err}");
+                        notify(toasts, message, Some(Action::SetAllowedSchemes { schemes }));
+                    }
+                }
+                Some(Action::CreateLibrary { name }) => {
+                    let id = local_libraries.create(&name);
+                    libraries.set(local_libraries.clone());
+
+                    match registry.put_libraries(local_libraries.clone()).await {
+                        Ok(()) => queued = Some(Action::SwitchLibrary { id }),
+                        Err(err) => {
+                            let message = format!("Couldn't save libraries. {err}");
+                            notify(toasts, message, None);
+                        }
+                    }
+                }
+                // Changes not written to the file yet would only be written once this library is
+                // open again, and then not without another edit
+                Some(Action::SwitchLibrary { id })
+                    if id != local_libraries.current && unsaved && handle.is_some() =>
+                {
+                    let message = "Save changes to the file before switching libraries.";
+                    notify(toasts, message.to_string(), Some(Action::Sync));
+                }
+                Some(Action::SwitchLibrary { id }) if id != local_libraries.current => {
+                    let opened = match id {
+                        LibraryId::DEFAULT => registry
+                            .load()
+                            .await
+                            .map(|stored| (registry.clone(), stored))
+                            .map_err(AppError::from),
+                        id => open_library(id).await,
+                    };
+
+                    match opened {
+                        Ok((opened, stored)) => {
+                            backend = opened;
+                            handle = stored.handle;
+                            sync_base = stored.sync_base;
+                            local_settings = stored.settings;
+                            settings.set(local_settings.clone());
+
+                            {
+                                let mut store_mut = store.write();
+
+                                *store_mut = Store::new();
+                                for collection in stored.collections {
+                                    store_mut.add_collection(collection);
+                                }
+                                for bookmark in stored.bookmarks {
+                                    store_mut.add_bookmark(bookmark);
+                                }
+
+                                // Ids of the old library, the UI renders before they are synced
+                                // again
+                                cards.write().clear();
+                                trash_cards.write().clear();
+                                collections.write().clear();
+                                selected_collection.set(None);
+                            }
+
+                            unsaved = false;
+                            save_status.set(SaveStatus::Idle);
+                            pending_merge.set(None);
+                            import_status.set(None);
+                            collection_filter = None;
+                            undo_stack = UndoStack::default();
+                            bookmark_history.set(None);
+                            duplicates.set(None);
+
+                            local_libraries.current = id;
+                            libraries.set(local_libraries.clone());
+
+                            if let Err(err) = registry.put_libraries(local_libraries.clone()).await
+                            {
+                                let message = format!("Couldn't save libraries. {err}");
+                                notify(toasts, message, None);
+                            }
+                        }
+                        Err(err) => {
+                            let message = format!("Couldn't open the library. {err}");
+                            notify(toasts, message, Some(Action::SwitchLibrary { id }));
+                        }
+                    }
+                }
+                Some(Action::SwitchLibrary { .. }) => {}
+                // Gone, or queued before the library was switched
+                Some(Action::CopyToLibrary { id, .. })
+                    if store.peek().try_bookmark(id).is_none() => {}
+                Some(Action::CopyToLibrary { id, library }) => {
+                    let mut bookmark = store.peek().bookmark(id).clone();
+                    bookmark.id = BookmarkId::new();
+                    // Collections belong to this library
+                    bookmark.collection = None;
+
+                    let result = match open_target(&registry, library).await {
+                        Ok(target) => target.put_bookmark(bookmark).await.map_err(AppError::from),
+                        Err(err) => Err(err),
+                    };
+                    if let Err(err) = result {
+                        let message = format!("Couldn't put the bookmark in the library. {err}");
+                        notify(toasts, message, Some(Action::CopyToLibrary { id, library }));
+                    }
+                }
+                Some(Action::MoveToLibrary { id, library }) => {
+                    moved_bookmarks.push((id, library));
+                }
+                Some(Action::CreateBookmark {
+                    title,
+                    link,
+                    note,
+                    tags,
+                }) => {
                     created_bookmarks.push(CreateBookmark {
                         title: title.to_owned(),
                         link: link.to_owned(),
                         note: note.to_owned(),
+                        tags,
                     });
                 }
-                Action::Sync => {
-                    let file_data = {
-                        let store_ref = store.peek();
-
-                        let to_local_file = ToLocalFile {
-                            version: LOCAL_FILE_VERSION,
-                            bookmarks: store_ref.all_data().collect(),
-                        };
-
-                        serde_json::to_string_pretty(&to_local_file)
-                            .expect("should be able to serialize")
-                    };
+                Some(Action::UpdateBookmark {
+                    id,
+                    title,
+                    link,
+                    note,
+                }) => {
+                    updated_bookmarks.push(UpdateBookmark {
+                        id,
+                        title,
+                        link,
+                        note,
+                    });
+                }
+                Some(Action::Import) => match import_file(&local_settings.allowed_schemes).await {
+                    Ok(pending) => import_preview.set(Some(pending)),
+                    Err(err) if err.is_cancelled() => {}
+                    Err(err) => {
+                        let message = format!("Import failed. {err}");
+                        notify(toasts, message, Some(Action::Import));
+                    }
+                },
+                Some(Action::ConfirmImport) => {
+                    imported_bookmarks = import_preview.write().take();
+                }
+                Some(sync @ (Action::Sync | Action::Autosave { .. })) => {
+                    // Only autosave in the background doesn't come from a click
+                    let request = !matches!(sync, Action::Autosave { request: false });
+                    let result =
+                        sync_file(&*backend, store, &mut handle, &mut sync_base, request).await;
 
-                    let returned_handle = save_to_file(handle.clone(), file_data)
-                        .await
-                        .expect("should be able to save");
+                    match result {
+                        Ok(Synced::Written) => {
+                            unsaved = false;
+                            save_status.set(SaveStatus::Saved(time::now()));
+                        }
+                        Ok(Synced::NeedsPermission) => save_status.set(SaveStatus::NeedsPermission),
+                        Ok(Synced::Diverged(merge, current)) => {
+                            merged_bookmarks.extend(merge.changes);
+                            merged_removals.extend(merge.removals);
+                            merged_collections.extend(merge.collection_changes);
+                            merged_collection_removals.extend(merge.collection_removals);
 
-                    if handle.is_none() {
-                        handle = Some(returned_handle.clone());
+                            if merge.conflicts.is_empty() {
+                                sync_base = Some(current);
+                                queued = Some(sync);
+                            } else {
+                                save_status.set(SaveStatus::Conflicted);
+                                pending_merge.set(Some(PendingMerge {
+                                    keep_theirs: vec![false; merge.conflicts.len()],
+                                    conflicts: merge.conflicts,
+                                    snapshot: current,
+                                }));
+                            }
+                        }
+                        Err(err) if err.is_cancelled() => {}
+                        Err(err) => {
+                            save_status.set(SaveStatus::Failed);
 
-                        let transaction = indexed_db
-                            .transaction(&["handles"], TransactionMode::ReadWrite)
-                            .expect("should be able to create transaction");
+                            let message = format!("Sync failed. {err}");
+                            notify(toasts, message, Some(sync));
+                        }
+                    }
+                }
+                Some(Action::ResolveConflicts) => {
+                    if let Some(pending) = pending_merge.write().take() {
+                        let choices = pending.conflicts.into_iter().zip(pending.keep_theirs);
+                        for (conflict, keep_theirs) in choices {
+                            match (keep_theirs, conflict.theirs, conflict.ours) {
+                                (false, _, _) => {}
+                                (true, Some(theirs), _) => merged_bookmarks.push(theirs),
+                                (true, None, Some(ours)) => merged_removals.push(ours.id),
+                                (true, None, None) => {}
+                            }
+                        }
 
-                        let handles_os = transaction
-                            .object_store("handles")
-                            .expect("should be able to access object store");
+                        sync_base = Some(pending.snapshot);
+                        queued = Some(Action::Sync);
+                    }
+                }
+                Some(Action::Export { exporter }) => {
+                    let file_data = {
+                        let store_ref = store.peek();
+                        exporter.export(
+                            &store_ref.all_data().collect::<Vec<_>>(),
+                            &store_ref.all_collections().collect::<Vec<_>>(),
+                        )
+                    };
 
-                        handles_os
-                            .put(&returned_handle, Some(&initial_handle_name))
-                            .expect("should be able to write the handle")
+                    let result = match file_data {
+                        Ok(file_data) => save_to_file(None, file_data, exporter.file_name())
                             .await
-                            .expect("should be able to write the handle");
+                            .map(|_| ())
+                            .map_err(AppError::from),
+                        Err(err) => Err(err.into()),
+                    };
 
-                        transaction
-                            .await
-                            .expect("transaction should be able to complete");
+                    match result {
+                        Ok(()) => {}
+                        Err(err) if err.is_cancelled() => {}
+                        Err(err) => {
+                            let message = format!("Export failed. {err}");
+                            notify(toasts, message, Some(Action::Export { exporter }));
+                        }
                     }
                 }
             }
 
             {
+                let now = time::now();
                 let mut store_mut = store.write();
-                for CreateBookmark { title, link, note } in created_bookmarks.drain(..) {
-                    store_mut.create_bookmark(&title, &link, &note);
+                // Actions come from what is on screen, a bookmark or collection may be gone by now
+                let bookmark_id =
+                    |store: &Store, id: ArenaId<Bookmark>| store.try_bookmark(id).map(|x| x.id);
+                let collection_id = |store: &Store, id: Option<ArenaId<Collection>>| {
+                    id.and_then(|x| store.try_collection(x)).map(|x| x.id)
+                };
+                // Edits made by this action, they are undone together
+                let mut commands = Vec::new();
+                for CreateBookmark {
+                    title,
+                    link,
+                    note,
+                    tags,
+                } in created_bookmarks.drain(..)
+                {
+                    let bookmark = Bookmark::new(&title, &link, &note, now);
+                    let id = bookmark.id;
+                    commands.push(Command::CreateBookmark(bookmark));
+                    for tag in tags {
+                        let tag = Rc::from(tag);
+                        commands.push(Command::TagBookmark { id, tag });
+                    }
+                }
+                for UpdateBookmark {
+                    id,
+                    title,
+                    link,
+                    note,
+                } in updated_bookmarks.drain(..)
+                {
+                    if let Some(id) = bookmark_id(&store_mut, id) {
+                        commands.push(Command::UpdateBookmark {
+                            id,
+                            title: Rc::from(title),
+                            link: Rc::from(link),
+                            note: Rc::from(note),
+                        });
+                    }
+                }
+                for id in deleted_bookmarks.drain(..) {
+                    if let Some(id) = bookmark_id(&store_mut, id) {
+                        commands.push(Command::DeleteBookmark(id));
+                    }
+                }
+                for id in restored_bookmarks.drain(..) {
+                    if let Some(id) = bookmark_id(&store_mut, id) {
+                        commands.push(Command::RestoreBookmark(id));
+                    }
+                }
+                for (id, tag) in tagged_bookmarks.drain(..) {
+                    if let Some(id) = bookmark_id(&store_mut, id) {
+                        let tag = Rc::from(tag);
+                        commands.push(Command::TagBookmark { id, tag });
+                    }
+                }
+                for (id, tag) in untagged_bookmarks.drain(..) {
+                    if let Some(id) = bookmark_id(&store_mut, id) {
+                        let tag = Rc::from(tag);
+                        commands.push(Command::UntagBookmark { id, tag });
+                    }
+                }
+                for (name, parent) in created_collections.drain(..) {
+                    let parent = collection_id(&store_mut, parent);
+                    commands.push(Command::CreateCollection(Collection::new(
+                        &name, parent, now,
+                    )));
+                }
+                for (id, parent) in moved_collections.drain(..) {
+                    let parent = collection_id(&store_mut, parent);
+                    if let Some(id) = collection_id(&store_mut, Some(id)) {
+                        commands.push(Command::MoveCollection { id, parent });
+                    }
+                }
+                for (id, collection) in filed_bookmarks.drain(..) {
+                    let collection = collection_id(&store_mut, collection);
+                    if let Some(id) = bookmark_id(&store_mut, id) {
+                        commands.push(Command::FileBookmark { id, collection });
+                    }
+                }
+                for id in deleted_collections.drain(..) {
+                    if let Some(id) = collection_id(&store_mut, Some(id)) {
+                        commands.push(Command::DeleteCollection(id));
+                    }
+                }
+                for ids in merged_duplicates.drain(..) {
+                    commands.extend(store_mut.merge_duplicates(&ids));
+                }
+                if let Some(pending) = imported_bookmarks.take() {
+                    let (imported, report) =
+                        store_mut.import(pending.bookmarks, pending.collections);
+                    commands.extend(imported);
+                    import_status.set(Some(format!(
+                        "Imported {} new, {} updated, {} duplicates skipped",
+                        report.added, report.updated, report.skipped,
+                    )));
+                }
+                for (id, library) in moved_bookmarks.drain(..) {
+                    if let Some(id) = bookmark_id(&store_mut, id) {
+                        commands.push(Command::MoveBookmark { id, library });
+                    }
+                }
+                for (id, entry) in restored_versions.drain(..) {
+                    if let Some(id) = bookmark_id(&store_mut, id) {
+                        commands.push(Command::UpdateBookmark {
+                            id,
+                            title: entry.title,
+                            link: entry.link,
+                            note: entry.note,
+                        });
+                    }
+                }
+                undo_stack.apply(&mut store_mut, commands, now);
+                if undo {
+                    undo_stack.undo(&mut store_mut, now);
+                }
+                if redo {
+                    undo_stack.redo(&mut store_mut, now);
+                }
+                (undo, redo) = (false, false);
+                can_undo.set(undo_stack.can_undo());
+                can_redo.set(undo_stack.can_redo());
+
+                for id in visited_bookmarks.drain(..) {
+                    if store_mut.try_bookmark(id).is_some() {
+                        store_mut.visit_bookmark(id, now);
+                    }
+                }
+                // Changes merged from the synced file were made elsewhere, undoing them would
+                // revert another device's edits. So, like visits, they are not undo steps.
+                for collection in merged_collections.drain(..) {
+                    store_mut.put_collection(collection);
+                }
+                for id in merged_collection_removals.drain(..) {
+                    store_mut.remove_collection(id, now);
+                }
+                for bookmark in merged_bookmarks.drain(..) {
+                    store_mut.put_bookmark(bookmark, now);
+                }
+                for id in merged_removals.drain(..) {
+                    if let Some(id) = store_mut.find(id) {
+                        store_mut.delete_bookmark(id, now);
+                    }
+                }
+                store_mut.purge_trash(now, local_settings.trash_retention());
+            }
+
+            // Before persisting, which deletes the history of moved bookmarks in this library
+            let transfers = store.write().transfers().map(|x| x.collect::<Vec<_>>());
+            for transfer in transfers.unwrap_or_default() {
+                let Err(err) = transfer_bookmark(&*backend, &registry, &transfer).await else {
+                    continue;
+                };
+                let message = format!("Couldn't move the bookmark. {err}");
+                match transfer {
+                    // Not in the other library, so it stays here
+                    Transfer::Out(bookmark, library) => {
+                        let id = store.write().create_bookmark(bookmark);
+                        notify(toasts, message, Some(Action::MoveToLibrary { id, library }));
+                    }
+                    Transfer::In(..) => {
+                        store.write().requeue_transfers(vec![transfer]);
+                        notify(toasts, message, Some(Action::Persist));
+                    }
                 }
             }
 
             // Sync to UI
             {
-                let store_ref = store.peek();
-                *cards.write() = store_ref.all_ids().collect();
+                let mut store_mut = store.write();
+
+                let mask = if tag_filter.trim().is_empty() {
+                    Ok(None)
+                } else {
+                    store_mut.query_tags(&tag_filter).map(Some)
+                };
+                let mut mask = match mask {
+                    Ok(mask) => {
+                        tag_query_error.set(None);
+                        mask
+                    }
+                    Err(err) => {
+                        tag_query_error.set(Some(err.to_string()));
+                        None
+                    }
+                };
+
+                collection_filter =
+                    collection_filter.filter(|x| store_mut.try_collection(*x).is_some());
+                if let Some(collection) = collection_filter {
+                    let collection_mask = store_mut.query_collection(collection);
+                    mask = Some(match mask {
+                        Some(mut mask) => {
+                            mask.and(&collection_mask);
+                            mask
+                        }
+                        None => collection_mask,
+                    });
+                }
+
+                *cards.write() = store_mut
+                    .search(&search_query, mask.as_ref(), sort_order)
+                    .to_vec();
+                *trash_cards.write() = store_mut.trash_ids().collect();
+                *tags.write() = store_mut.tags();
+                *collections.write() = store_mut.collection_tree();
+                if duplicates.peek().is_some() {
+                    duplicates.set(Some(duplicate_groups(&store_mut)));
+                }
+                selected_collection.set(collection_filter);
+                *store_version.write() += 1;
             }
 
-            let changes = {
+            let (changes, removals, collection_changes, collection_removals, history) = {
                 let mut store_mut = store.write();
-                store_mut.changes().map(|x| x.collect::<Vec<_>>())
+                let changes = store_mut.changes().map(|x| x.collect::<Vec<_>>());
+                let removals = store_mut.removals().map(|x| x.collect::<Vec<_>>());
+                let collection_changes = store_mut
+                    .collection_changes()
+                    .map(|x| x.collect::<Vec<_>>());
+                let collection_removals = store_mut
+                    .collection_removals()
+                    .map(|x| x.collect::<Vec<_>>());
+                let history = store_mut.history_changes().map(|x| x.collect::<Vec<_>>());
+                (
+                    changes,
+                    removals,
+                    collection_changes,
+                    collection_removals,
+                    history,
+                )
             };
 
             // Sync to IndexedDB
-            {
-                let store_ref = store.peek();
+            let changes = changes.unwrap_or_default();
+            let removals = removals.unwrap_or_default();
+            let collection_changes = collection_changes.unwrap_or_default();
+            let collection_removals = collection_removals.unwrap_or_default();
+            let history = history.unwrap_or_default();
 
-                if let Some(changes) = changes {
-                    let transaction = indexed_db
-                        .transaction(&["bookmarks"], TransactionMode::ReadWrite)
-                        .expect("should be able to create transaction");
-
-                    let bookmarks = transaction
-                        .object_store("bookmarks")
-                        .expect("should be able to access object store");
-
-                    for id in changes {
-                        let bookmark = store_ref.bookmark(id);
-                        let bookmark = bookmark
-                            .serialize(&serializer)
-                            .expect("should be able to serialize");
-                        bookmarks
-                            .put(&bookmark, Some(&JsValue::from_f64(id.id() as f64)))
-                            .expect("should be able to write bookmark")
-                            .await
-                            .expect("should be able to write bookmark");
+            let has_changes = !changes.is_empty()
+                || !removals.is_empty()
+                || !collection_changes.is_empty()
+                || !collection_removals.is_empty();
+            if has_changes {
+                unsaved = true;
+
+                // Only shown to those who write to a file
+                let is_syncing = local_settings.autosave || handle.is_some();
+                let is_blocked = matches!(
+                    *save_status.peek(),
+                    SaveStatus::NeedsPermission | SaveStatus::Conflicted
+                );
+                if is_syncing && !is_blocked {
+                    save_status.set(match handle {
+                        Some(_) => SaveStatus::Unsaved,
+                        None => SaveStatus::NoFile,
+                    });
+                }
+            }
+
+            let result = persist(
+                &*backend,
+                store,
+                &changes,
+                &removals,
+                &collection_changes,
+                &collection_removals,
+                &history,
+            )
+            .await;
+            if let Err(err) = result {
+                // Kept as changes, so they are written with the next action or retry
+                {
+                    let mut store_mut = store.write();
+                    store_mut.requeue(changes, removals);
+                    store_mut.requeue_collections(collection_changes, collection_removals);
+                    store_mut.requeue_history(history);
+                }
+
+                let message = format!("Couldn't save changes. {err}");
+                notify(toasts, message, Some(Action::Persist));
+            } else if has_changes {
+                // An open history panel shows the edits just written, it closes if the bookmark
+                // is gone
+                let shown = bookmark_history.peek().as_ref().map(|x| x.id);
+                if let Some(id) = shown {
+                    match load_history(&*backend, store, id).await {
+                        Ok(loaded) => bookmark_history.set(loaded),
+                        Err(err) => {
+                            let message = format!("Couldn't load history. {err}");
+                            notify(toasts, message, Some(Action::ShowHistory { id }));
+                        }
                     }
+                }
+            }
+
+            let is_autosave_due = unsaved
+                && local_settings.autosave
+                && handle.is_some()
+                && *save_status.peek() == SaveStatus::Unsaved;
 
-                    transaction
-                        .await
-                        .expect("transaction should be able to complete");
+            // Autosaves once no action came for a while, any action restarts the wait
+            let next = if let Some(queued) = queued.take() {
+                Some(queued)
+            } else if is_autosave_due {
+                match select(rx.next(), pin!(time::sleep(AUTOSAVE_DELAY))).await {
+                    Either::Left((next, _)) => next,
+                    Either::Right(_) => Some(Action::Autosave { request: false }),
                 }
+            } else {
+                rx.next().await
+            };
+
+            match next {
+                Some(next) => action = Some(next),
+                None => break,
             }
         }
     });
+    use_undo_shortcuts(coroutine);
 
-    let is_drawer_disabled = use_memo(move || {
-        let title = || drawer_title().is_empty();
-        let http = || drawer_link().starts_with("http://");
-        let https = || drawer_link().starts_with("https://");
-
-        title() || !(http() || https())
+    let other_libraries = use_memo(move || {
+        let libraries = libraries.read();
+        libraries
+            .libraries
+            .iter()
+            .filter(|x| x.id != libraries.current)
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+    let drawer_parsed_link =
+        use_memo(move || link::parse(&drawer_link(), &settings.read().allowed_schemes));
+    // Nothing typed yet is not an error to show
+    let drawer_link_error = use_memo(move || match drawer_parsed_link() {
+        Err(err) if !drawer_link().trim().is_empty() => Some(err.to_string()),
+        _ => None,
+    });
+    let is_drawer_disabled =
+        use_memo(move || drawer_title().is_empty() || drawer_parsed_link().is_err());
+    // Title of a bookmark that has the link already, adding it anyway is allowed
+    let drawer_duplicate = use_memo(move || {
+        store_version();
+        let link = drawer_parsed_link().ok()?;
+        let store_ref = store.peek();
+        let id = store_ref.find_by_link(&link).into_iter().next()?;
+        Some(store_ref.bookmark(id).title.to_string())
     });
 
     let onclick = move |_| {
+        let Ok(link) = drawer_parsed_link() else {
+            return;
+        };
         coroutine.send(Action::CreateBookmark {
             title: drawer_title.cloned(),
-            link: drawer_link.cloned(),
+            link,
             note: drawer_note.cloned(),
+            tags: parse_tags(&drawer_tags()),
         });
 
         drawer_title.set(String::new());
         drawer_link.set(String::new());
         drawer_note.set(String::new());
+        drawer_tags.set(String::new());
+    };
+
+    let view_toggle = match view() {
+        View::Bookmarks => "Trash".to_string(),
+        View::Trash => "Bookmarks".to_string(),
     };
 
     rsx! {
@@ -276,6 +1299,18 @@ fn App() -> Element {
                             value: drawer_link,
                             oninput: move |ev| drawer_link.set(ev.value()),
                         }
+                        if let Some(err) = drawer_link_error() {
+                            span {
+                                class: "-mt-4 text-sm text-red-600",
+                                "{err}"
+                            }
+                        }
+                        if let Some(title) = drawer_duplicate() {
+                            span {
+                                class: "-mt-4 text-sm text-amber-600",
+                                "Already saved as “{title}”"
+                            }
+                        }
                         textarea {
                             class: "bg-primary px-4 py-2 rounded-md border border-gray-300 resize-none",
                             rows: 16,
@@ -283,19 +1318,53 @@ fn App() -> Element {
                             value: drawer_note,
                             oninput: move |ev| drawer_note.set(ev.value()),
                         }
+                        input {
+                            class: "bg-primary px-4 h-8 rounded-md border border-gray-300",
+                            placeholder: "Tags, comma separated",
+                            value: drawer_tags,
+                            oninput: move |ev| drawer_tags.set(ev.value()),
+                        }
                         Button {
                             value: "Add",
                             size: ButtonSize::Full,
                             disabled: is_drawer_disabled(),
                             onclick,
                         }
+                        label {
+                            class: "flex flex-col gap-2 text-sm text-gray-500",
+                            "Allowed link schemes, comma separated"
+                            input {
+                                class: "bg-primary px-4 h-8 rounded-md border border-gray-300 text-black",
+                                value: settings.read().allowed_schemes.join(", "),
+                                onchange: move |ev| {
+                                    coroutine.send(Action::SetAllowedSchemes {
+                                        schemes: parse_schemes(&ev.value()),
+                                    })
+                                },
+                            }
+                        }
                     }
                 }
             }
+            CollectionTree {
+                nodes: collections(),
+                selected: selected_collection(),
+                dragged,
+            }
+            TagList {
+                tags: tags(),
+                query: tag_query(),
+                error: tag_query_error(),
+                onquery: move |query: String| {
+                    tag_query.set(query.clone());
+                    coroutine.send(Action::QueryTags { query });
+                },
+            }
             div {
                 class: "flex-[3] flex flex-col",
                 div {
                     class: "sticky top-0 h-16 w-full bg-primary flex items-center border-b border-gray-200",
+                    LibrarySwitcher { libraries: libraries() }
                     span {
                         class: "mx-8",
                         Button {
@@ -304,6 +1373,29 @@ fn App() -> Element {
                             onclick: move |_| drawer_enabled.set(!drawer_enabled()),
                         }
                     }
+                    span {
+                        class: "mx-8 flex gap-2",
+                        Button {
+                            value: "Undo",
+                            size: ButtonSize::Small,
+                            disabled: !can_undo(),
+                            onclick: move |_| coroutine.send(Action::Undo),
+                        }
+                        Button {
+                            value: "Redo",
+                            size: ButtonSize::Small,
+                            disabled: !can_redo(),
+                            onclick: move |_| coroutine.send(Action::Redo),
+                        }
+                    }
+                    span {
+                        class: "mx-8",
+                        Button {
+                            value: "Open",
+                            size: ButtonSize::Big,
+                            onclick: move |_| coroutine.send(Action::Import),
+                        }
+                    }
                     span {
                         class: "mx-8",
                         Button {
@@ -312,46 +1404,306 @@ fn App() -> Element {
                             onclick: move |_| coroutine.send(Action::Sync),
                         }
                     }
+                    span {
+                        class: "mx-8",
+                        Button {
+                            value: "Duplicates",
+                            size: ButtonSize::Big,
+                            onclick: move |_| coroutine.send(Action::FindDuplicates),
+                        }
+                    }
+                    AutosaveStatus {
+                        enabled: settings().autosave,
+                        status: save_status(),
+                    }
+                    ExportMenu {}
+                    span {
+                        class: "mx-8",
+                        Button {
+                            value: view_toggle,
+                            size: ButtonSize::Big,
+                            onclick: move |_| {
+                                view.set(match view() {
+                                    View::Bookmarks => View::Trash,
+                                    View::Trash => View::Bookmarks,
+                                })
+                            },
+                        }
+                    }
+                    if let Some(status) = import_status() {
+                        span {
+                            class: "mr-8 text-sm text-gray-500",
+                            "{status}"
+                        }
+                    }
                     input {
                         class: "bg-primary flex-1 px-4 h-8 rounded-md border border-gray-300",
-                        placeholder: "Search"
+                        placeholder: "Search",
+                        oninput: move |ev| coroutine.send(Action::Search { query: ev.value() }),
+                    }
+                    select {
+                        class: "bg-primary mx-8 px-2 h-8 rounded-md border border-gray-300",
+                        onchange: move |ev| {
+                            let sort = SortOrder::ALL.into_iter().find(|x| x.label() == ev.value());
+                            if let Some(sort) = sort {
+                                coroutine.send(Action::SetSort { sort });
+                            }
+                        },
+                        for sort in SortOrder::ALL {
+                            option {
+                                key: "{sort.label()}",
+                                value: sort.label(),
+                                {sort.label()}
+                            }
+                        }
                     }
                 }
-                div {
-                    class: "flex-1 w-full grid grid-cols-cards grid-rows-[min-content] p-8 gap-8",
-                    for id in cards() {
-                        Card {
-                            store,
-                            id,
+                if view() == View::Trash {
+                    Trash {
+                        bookmarks: trash_cards()
+                            .into_iter()
+                            .filter_map(|id| Some((id, store.peek().try_bookmark(id)?.clone())))
+                            .collect::<Vec<_>>(),
+                        retention_days: settings().trash_retention_days,
+                    }
+                } else {
+                    div {
+                        class: "flex-1 w-full grid grid-cols-cards grid-rows-[min-content] p-8 gap-8",
+                        for (id, bookmark) in cards().into_iter().filter_map(|id| Some((id, store.peek().try_bookmark(id)?.clone()))) {
+                            Card {
+                                key: "{bookmark.id}",
+                                id,
+                                bookmark,
+                                libraries: other_libraries(),
+                                schemes: settings.read().allowed_schemes.clone(),
+                                dragged,
+                            }
                         }
                     }
                 }
             }
         }
+        ImportPreview { preview: import_preview }
+        HistoryPanel { history: bookmark_history }
+        DuplicatesDialog { groups: duplicates }
+        ConflictDialog { pending: pending_merge }
+        Toasts { toasts }
     }
 }
 
+/// Can be dragged onto a collection in [`CollectionTree`]. `libraries` are the ones the bookmark
+/// can be copied or moved to, `schemes` the ones an edited link can have.
 #[component]
-fn Card(store: Signal<Store>, id: ArenaId<Bookmark>) -> Element {
-    let bookmark = use_memo(move || store.peek().bookmark(id).clone());
+fn Card(
+    id: ArenaId<Bookmark>,
+    bookmark: Bookmark,
+    libraries: Vec<Library>,
+    schemes: Vec<String>,
+    dragged: Signal<Option<Dragged>>,
+) -> Element {
+    let coroutine = use_coroutine_handle::<Action>();
+
+    let mut dragged = dragged;
+    let mut editing = use_signal(|| false);
+    let mut edit_title = use_signal(|| String::new());
+    let mut edit_link = use_signal(|| String::new());
+    let mut edit_note = use_signal(|| String::new());
+
+    let mut new_tag = use_signal(|| String::new());
+
+    if editing() {
+        let parsed_link = link::parse(&edit_link(), &schemes);
+        let is_edit_disabled = edit_title().is_empty() || parsed_link.is_err();
+        let link_error = parsed_link.as_ref().err().map(|x| x.to_string());
+
+        return rsx! {
+            div {
+                class: "flex flex-col gap-2 px-4 py-2 bg-primary shadow-md h-80 rounded-xl",
+                input {
+                    class: "bg-primary px-4 h-8 rounded-md border border-gray-300",
+                    placeholder: "Title",
+                    value: edit_title,
+                    oninput: move |ev| edit_title.set(ev.value()),
+                }
+                input {
+                    class: "bg-primary px-4 h-8 rounded-md border border-gray-300",
+                    placeholder: "Link",
+                    value: edit_link,
+                    oninput: move |ev| edit_link.set(ev.value()),
+                }
+                if let Some(err) = link_error {
+                    span {
+                        class: "text-xs text-red-600",
+                        "{err}"
+                    }
+                }
+                textarea {
+                    class: "flex-1 bg-primary px-4 py-2 rounded-md border border-gray-300 resize-none",
+                    placeholder: "Note",
+                    value: edit_note,
+                    oninput: move |ev| edit_note.set(ev.value()),
+                }
+                div {
+                    class: "flex gap-2 justify-end",
+                    Button {
+                        value: "Cancel",
+                        size: ButtonSize::Small,
+                        onclick: move |_| editing.set(false),
+                    }
+                    Button {
+                        value: "Save",
+                        size: ButtonSize::Small,
+                        disabled: is_edit_disabled,
+                        onclick: move |_| {
+                            let Ok(link) = parsed_link.clone() else {
+                                return;
+                            };
+                            coroutine.send(Action::UpdateBookmark {
+                                id,
+                                title: edit_title.cloned(),
+                                link,
+                                note: edit_note.cloned(),
+                            });
+                            editing.set(false);
+                        },
+                    }
+                }
+            }
+        };
+    }
+
+    let onedit = {
+        let bookmark = bookmark.clone();
+        move |_| {
+            edit_title.set(bookmark.title.to_string());
+            edit_link.set(bookmark.link.to_string());
+            edit_note.set(bookmark.note.to_string());
+            editing.set(true);
+        }
+    };
 
     rsx! {
         div {
             class: "flex flex-col px-4 py-2 bg-primary shadow-md h-80 rounded-xl break-words",
-            h3 {
-                class: "h-16 text-sm font-bold",
-                {bookmark().title}
+            draggable: true,
+            ondragstart: move |_| dragged.set(Some(Dragged::Bookmark(id))),
+            ondragend: move |_| dragged.set(None),
+            div {
+                class: "h-16 flex gap-2",
+                h3 {
+                    class: "flex-1 text-sm font-bold",
+                    {bookmark.title.to_string()}
+                }
+                Button {
+                    value: "Edit",
+                    size: ButtonSize::Small,
+                    onclick: onedit,
+                }
+                Button {
+                    value: "History",
+                    size: ButtonSize::Small,
+                    onclick: move |_| coroutine.send(Action::ShowHistory { id }),
+                }
+                Button {
+                    value: "Delete",
+                    size: ButtonSize::Small,
+                    onclick: move |_| coroutine.send(Action::DeleteBookmark { id }),
+                }
+                if !libraries.is_empty() {
+                    select {
+                        class: "bg-primary w-8 h-6 rounded-md border border-gray-300 text-xs",
+                        title: "Copy or move to another library",
+                        value: "",
+                        onchange: {
+                            let libraries = libraries.clone();
+                            move |ev: FormEvent| {
+                                let value = ev.value();
+                                let Some((kind, library)) = value.split_once(':') else {
+                                    return;
+                                };
+                                let Some(library) = libraries
+                                    .iter()
+                                    .map(|x| x.id)
+                                    .find(|x| x.to_string() == library)
+                                else {
+                                    return;
+                                };
+                                match kind {
+                                    "copy" => coroutine.send(Action::CopyToLibrary { id, library }),
+                                    "move" => coroutine.send(Action::MoveToLibrary { id, library }),
+                                    _ => {}
+                                }
+                            }
+                        },
+                        option {
+                            value: "",
+                            "…"
+                        }
+                        for library in libraries.iter() {
+                            option {
+                                key: "copy:{library.id}",
+                                value: "copy:{library.id}",
+                                "Copy to {library.name}"
+                            }
+                            option {
+                                key: "move:{library.id}",
+                                value: "move:{library.id}",
+                                "Move to {library.name}"
+                            }
+                        }
+                    }
+                }
             }
             div {
                 a {
                     class: "text-sky-500 underline break-words",
-                    href: "{bookmark().link}",
-                    {bookmark().link}
+                    href: "{bookmark.link}",
+                    target: "_blank",
+                    rel: "noopener noreferrer",
+                    onclick: move |_| coroutine.send(Action::VisitBookmark { id }),
+                    {link::display(&bookmark.link)}
                 }
             }
             div {
                 class: "flex-1 break-words",
-                {bookmark().note}
+                {bookmark.note.to_string()}
+            }
+            div {
+                class: "flex flex-wrap items-center gap-2 py-2",
+                for tag in bookmark.tags.iter().cloned() {
+                    span {
+                        key: "{tag}",
+                        class: "flex items-center gap-1 px-2 rounded-md bg-tertiary text-xs",
+                        "{tag}"
+                        span {
+                            class: "cursor-pointer select-none text-gray-500",
+                            onclick: {
+                                let tag = tag.to_string();
+                                move |_| {
+                                    coroutine.send(Action::UntagBookmark {
+                                        id,
+                                        tag: tag.clone(),
+                                    })
+                                }
+                            },
+                            "×"
+                        }
+                    }
+                }
+                input {
+                    class: "bg-primary w-24 px-2 h-6 rounded-md border border-gray-300 text-xs",
+                    placeholder: "Add tag",
+                    value: new_tag,
+                    oninput: move |ev| new_tag.set(ev.value()),
+                    onkeydown: move |ev| {
+                        if ev.key() == Key::Enter {
+                            for tag in parse_tags(&new_tag()) {
+                                coroutine.send(Action::TagBookmark { id, tag });
+                            }
+                            new_tag.set(String::new());
+                        }
+                    },
+                }
             }
         }
     }
@@ -0,0 +1,336 @@
+//! Three-way merge of the library with the synced file, for when the file was changed elsewhere
+//! since sync last wrote it. Base is what sync last wrote, ours is the library and theirs is the
+//! file as it is now.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::store::{Bookmark, BookmarkId, Collection, CollectionId};
+
+/// Changed differently on both sides. None if that side deleted it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Conflict {
+    pub ours: Option<Bookmark>,
+    pub theirs: Option<Bookmark>,
+}
+
+#[derive(Default, PartialEq, Debug)]
+pub struct Merge {
+    /// Added or changed only in theirs, to be put in the library
+    pub changes: Vec<Bookmark>,
+    /// Deleted only in theirs, to be moved to trash
+    pub removals: Vec<BookmarkId>,
+    pub conflicts: Vec<Conflict>,
+    /// Added or changed only in theirs, see [`merge_collections`]
+    pub collection_changes: Vec<Collection>,
+    /// Deleted only in theirs
+    pub collection_removals: Vec<CollectionId>,
+}
+
+impl Merge {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+            && self.removals.is_empty()
+            && self.conflicts.is_empty()
+            && self.collection_changes.is_empty()
+            && self.collection_removals.is_empty()
+    }
+}
+
+/// Bookmarks are matched by id. A side that didn't change a bookmark takes the other side's
+/// version, only timestamps changing is not a change.
+pub fn merge(base: &[Bookmark], ours: &[&Bookmark], theirs: &[Bookmark]) -> Merge {
+    let base: BTreeMap<BookmarkId, &Bookmark> = base.iter().map(|x| (x.id, x)).collect();
+    let ours: BTreeMap<BookmarkId, &Bookmark> = ours.iter().map(|x| (x.id, *x)).collect();
+    let theirs: BTreeMap<BookmarkId, &Bookmark> = theirs.iter().map(|x| (x.id, x)).collect();
+
+    let ids: BTreeSet<BookmarkId> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .copied()
+        .collect();
+
+    let mut merge = Merge::default();
+    for id in ids {
+        let base = base.get(&id).copied();
+        let ours = ours.get(&id).copied();
+        let theirs = theirs.get(&id).copied();
+
+        if is_same(ours, theirs) || is_same(base, theirs) {
+            continue;
+        }
+
+        if is_same(base, ours) {
+            match theirs {
+                Some(theirs) => merge.changes.push(theirs.clone()),
+                None => merge.removals.push(id),
+            }
+        } else {
+            merge.conflicts.push(Conflict {
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+        }
+    }
+
+    merge
+}
+
+/// Fills in [`Merge::collection_changes`] and [`Merge::collection_removals`] the same way as
+/// bookmarks. Collections changed on both sides keep ours, there is nothing to pick from.
+pub fn merge_collections(
+    merge: &mut Merge,
+    base: &[Collection],
+    ours: &[&Collection],
+    theirs: &[Collection],
+) {
+    let base: BTreeMap<CollectionId, &Collection> = base.iter().map(|x| (x.id, x)).collect();
+    let ours: BTreeMap<CollectionId, &Collection> = ours.iter().map(|x| (x.id, *x)).collect();
+    let theirs: BTreeMap<CollectionId, &Collection> = theirs.iter().map(|x| (x.id, x)).collect();
+
+    let ids: BTreeSet<CollectionId> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .copied()
+        .collect();
+
+    let is_same = |a: Option<&Collection>, b: Option<&Collection>| match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => a.name == b.name && a.parent == b.parent,
+        _ => false,
+    };
+
+    for id in ids {
+        let base = base.get(&id).copied();
+        let ours = ours.get(&id).copied();
+        let theirs = theirs.get(&id).copied();
+
+        if !is_same(ours, theirs) && !is_same(base, theirs) && is_same(base, ours) {
+            match theirs {
+                Some(theirs) => merge.collection_changes.push(theirs.clone()),
+                None => merge.collection_removals.push(id),
+            }
+        }
+    }
+}
+
+/// Same title, link, note, collection and tags, in any order
+fn is_same(a: Option<&Bookmark>, b: Option<&Bookmark>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => {
+            a.title == b.title
+                && a.link == b.link
+                && a.note == b.note
+                && a.collection == b.collection
+                && a.tags.len() == b.tags.len()
+                && a.tags.iter().all(|x| b.tags.contains(x))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge, merge_collections, Conflict, Merge};
+    use crate::store::{Bookmark, BookmarkId, Collection, CollectionId};
+
+    #[test]
+    fn takes_changes_of_one_side() {
+        let [a, b, c, d, e] = [(); 5].map(|_| BookmarkId::new());
+
+        let base = vec![
+            Bookmark {
+                id: a,
+                ..Bookmark::new("a", "https://example.com", "", 0)
+            },
+            Bookmark {
+                id: b,
+                ..Bookmark::new("b", "https://example.com", "", 0)
+            },
+            Bookmark {
+                id: c,
+                ..Bookmark::new("c", "https://example.com", "", 0).with_tags(&["x", "y"])
+            },
+            Bookmark {
+                id: d,
+                ..Bookmark::new("d", "https://example.com", "", 0)
+            },
+        ];
+        let mut visited = Bookmark {
+            id: d,
+            ..Bookmark::new("d", "https://example.com", "", 0)
+        };
+        visited.last_visited_at = Some(1);
+        let ours = [
+            // Changed in ours only
+            Bookmark {
+                id: a,
+                ..Bookmark::new("a2", "https://example.com", "", 0)
+            },
+            Bookmark {
+                id: b,
+                ..Bookmark::new("b", "https://example.com", "", 0)
+            },
+            Bookmark {
+                id: c,
+                ..Bookmark::new("c", "https://example.com", "", 0).with_tags(&["x", "y"])
+            },
+            visited,
+        ];
+        let theirs = vec![
+            Bookmark {
+                id: a,
+                ..Bookmark::new("a", "https://example.com", "", 0)
+            },
+            // Tags in another order, not a change
+            Bookmark {
+                id: c,
+                ..Bookmark::new("c", "https://example.com", "", 0).with_tags(&["y", "x"])
+            },
+            Bookmark {
+                id: d,
+                ..Bookmark::new("d2", "https://example.com", "", 0)
+            },
+            Bookmark {
+                id: e,
+                ..Bookmark::new("e", "https://example.com", "", 0)
+            },
+        ];
+
+        let mut merged = merge(&base, &ours.iter().collect::<Vec<_>>(), &theirs);
+        merged.changes.sort_by_key(|x| x.title.clone());
+
+        assert_eq!(
+            merged,
+            Merge {
+                changes: vec![
+                    Bookmark {
+                        id: d,
+                        ..Bookmark::new("d2", "https://example.com", "", 0)
+                    },
+                    Bookmark {
+                        id: e,
+                        ..Bookmark::new("e", "https://example.com", "", 0)
+                    }
+                ],
+                removals: vec![b],
+                conflicts: Vec::new(),
+                ..Merge::default()
+            }
+        );
+    }
+
+    #[test]
+    fn conflicts_when_both_sides_change() {
+        let [a, b, c] = [(); 3].map(|_| BookmarkId::new());
+
+        let base = vec![
+            Bookmark {
+                id: a,
+                ..Bookmark::new("a", "https://example.com", "", 0)
+            },
+            Bookmark {
+                id: b,
+                ..Bookmark::new("b", "https://example.com", "", 0)
+            },
+            Bookmark {
+                id: c,
+                ..Bookmark::new("c", "https://example.com", "", 0)
+            },
+        ];
+        let ours = [
+            Bookmark {
+                id: a,
+                ..Bookmark::new("a2", "https://example.com", "", 0)
+            },
+            Bookmark {
+                id: c,
+                ..Bookmark::new("c2", "https://example.com", "", 0)
+            },
+        ];
+        let theirs = vec![
+            Bookmark {
+                id: a,
+                ..Bookmark::new("a3", "https://example.com", "", 0)
+            },
+            // Changed the same way
+            Bookmark {
+                id: c,
+                ..Bookmark::new("c2", "https://example.com", "", 0)
+            },
+            // Deleted in ours, changed in theirs
+            Bookmark {
+                id: b,
+                ..Bookmark::new("b", "https://example.com", "", 0).with_tags(&["x"])
+            },
+        ];
+
+        let merged = merge(&base, &ours.iter().collect::<Vec<_>>(), &theirs);
+
+        assert!(merged.changes.is_empty());
+        assert!(merged.removals.is_empty());
+        let mut conflicts = merged.conflicts;
+        conflicts.sort_by_key(|x| x.theirs.as_ref().map(|x| x.title.clone()));
+        assert_eq!(
+            conflicts,
+            vec![
+                Conflict {
+                    ours: Some(Bookmark {
+                        id: a,
+                        ..Bookmark::new("a2", "https://example.com", "", 0)
+                    }),
+                    theirs: Some(Bookmark {
+                        id: a,
+                        ..Bookmark::new("a3", "https://example.com", "", 0)
+                    }),
+                },
+                Conflict {
+                    ours: None,
+                    theirs: Some(Bookmark {
+                        id: b,
+                        ..Bookmark::new("b", "https://example.com", "", 0).with_tags(&["x"])
+                    }),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_collections_changed_in_theirs() {
+        let collection = |id: CollectionId, name: &str| Collection {
+            id,
+            name: name.into(),
+            parent: None,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let [a, b, c, d] = [(); 4].map(|_| CollectionId::new());
+
+        let base = vec![collection(a, "a"), collection(b, "b"), collection(c, "c")];
+        let ours = [
+            collection(a, "a"),
+            collection(b, "b"),
+            // Renamed on both sides, ours is kept
+            collection(c, "c2"),
+        ];
+        let theirs = vec![collection(a, "a2"), collection(c, "c3"), collection(d, "d")];
+
+        let mut merged = Merge::default();
+        merge_collections(
+            &mut merged,
+            &base,
+            &ours.iter().collect::<Vec<_>>(),
+            &theirs,
+        );
+        merged.collection_changes.sort_by_key(|x| x.name.clone());
+
+        assert_eq!(
+            merged.collection_changes,
+            vec![collection(a, "a2"), collection(d, "d")]
+        );
+        assert_eq!(merged.collection_removals, vec![b]);
+        assert!(merged.conflicts.is_empty());
+    }
+}
@@ -0,0 +1,457 @@
+//! Netscape bookmark file, the `bookmarks.html` every browser exports.
+//!
+//! It's HTML that isn't well formed, so it is read tag by tag instead of as a tree. `DL` opens a
+//! folder named by the `H3` before it, `A` is a bookmark and a `DD` right after it is its note.
+//! Folders become tags, except the ones browsers add by themselves. When writing, folders are
+//! [`crate::export::folders`] and all of a bookmark's tags are listed in `TAGS`.
+
+use std::{collections::HashMap, fmt::Write, rc::Rc};
+
+use crate::{
+    export::Folder,
+    link,
+    store::{normalize_tag, Bookmark, BookmarkId},
+    time::{Timestamp, SECOND},
+};
+
+const DOCTYPE: &str = "<!DOCTYPE NETSCAPE-Bookmark-file-1>";
+
+/// Attributes browsers put on folders they manage, like the bookmarks bar
+const SPECIAL_FOLDERS: &[&str] = &["PERSONAL_TOOLBAR_FOLDER", "UNFILED_BOOKMARKS_FOLDER"];
+
+pub fn is_netscape(file_data: &str) -> bool {
+    let start = file_data.trim_start().get(..DOCTYPE.len());
+    start.is_some_and(|x| x.eq_ignore_ascii_case(DOCTYPE))
+}
+
+enum Element {
+    Folder {
+        is_special: bool,
+    },
+    Link(HashMap<String, String>),
+    /// Note of the last bookmark
+    Description,
+}
+
+/// Bookmarks whose link [`link::parse`] takes with `schemes`, saved as it returns it. Others like
+/// bookmarklets are left out.
+pub fn parse(file_data: &str, schemes: &[String]) -> Vec<Bookmark> {
+    let mut bookmarks: Vec<Bookmark> = Vec::new();
+    // Tag of every open folder, None for the root and special folders
+    let mut folders: Vec<Option<String>> = Vec::new();
+    // Folder whose `H3` is read, but its `DL` isn't yet
+    let mut next_folder: Option<String> = None;
+    let mut element: Option<Element> = None;
+    let mut text = String::new();
+    // Whether a `DD` would be the note of the last bookmark
+    let mut after_link = false;
+
+    let mut rest = file_data;
+    while let Some(start) = rest.find('<') {
+        if element.is_some() {
+            text.push_str(&rest[..start]);
+        }
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+
+        if let Some(Element::Description) = element {
+            element = None;
+            if let Some(bookmark) = bookmarks.last_mut() {
+                bookmark.note = Rc::from(decode(text.trim()));
+            }
+            after_link = false;
+        }
+
+        let (name, attributes) = match tag.split_once(char::is_whitespace) {
+            Some((name, attributes)) => (name, attributes),
+            None => (tag, ""),
+        };
+
+        match name.to_ascii_uppercase().as_str() {
+            "H3" => {
+                let attributes = parse_attributes(attributes);
+                let is_special = SPECIAL_FOLDERS.iter().any(|x| attributes.contains_key(*x));
+                element = Some(Element::Folder { is_special });
+                text.clear();
+            }
+            "/H3" => {
+                if let Some(Element::Folder { is_special }) = element.take() {
+                    let tag = normalize_tag(&decode(&text));
+                    next_folder = Some(tag).filter(|x| !is_special && !x.is_empty());
+                }
+            }
+            "DL" => {
+                folders.push(next_folder.take());
+            }
+            "/DL" => {
+                folders.pop();
+            }
+            "A" => {
+                element = Some(Element::Link(parse_attributes(attributes)));
+                text.clear();
+            }
+            "/A" => {
+                if let Some(Element::Link(attributes)) = element.take() {
+                    let tags = folders.iter().flatten().map(String::as_str);
+                    if let Some(bookmark) = to_bookmark(&text, &attributes, tags, schemes) {
+                        bookmarks.push(bookmark);
+                        after_link = true;
+                    }
+                }
+            }
+            "DD" if after_link => {
+                element = Some(Element::Description);
+                text.clear();
+            }
+            "DT" => {
+                after_link = false;
+            }
+            _ => {}
+        }
+    }
+
+    bookmarks
+}
+
+fn to_bookmark<'a>(
+    text: &str,
+    attributes: &'a HashMap<String, String>,
+    folders: impl Iterator<Item = &'a str>,
+    schemes: &[String],
+) -> Option<Bookmark> {
+    let href = attributes.get("HREF")?;
+    let link = link::parse(href, schemes).ok()?;
+
+    let title = decode(text.trim());
+    let title = if title.is_empty() {
+        href.clone()
+    } else {
+        title
+    };
+
+    // Seconds in the file
+    let timestamp = |key: &str| {
+        attributes
+            .get(key)
+            .and_then(|x| x.parse::<Timestamp>().ok())
+            .map(|x| x * SECOND)
+    };
+    let created_at = timestamp("ADD_DATE").unwrap_or_default();
+
+    let mut tags: Vec<Rc<str>> = Vec::new();
+    let listed_tags = attributes
+        .get("TAGS")
+        .map(String::as_str)
+        .unwrap_or_default();
+    for tag in listed_tags.split(',').chain(folders) {
+        let tag = normalize_tag(tag);
+        if !tag.is_empty() && !tags.iter().any(|x| **x == *tag) {
+            tags.push(Rc::from(tag));
+        }
+    }
+
+    Some(Bookmark {
+        id: BookmarkId::new(),
+        title: Rc::from(title),
+        link: Rc::from(link.as_str()),
+        note: Rc::from(""),
+        created_at,
+        updated_at: timestamp("LAST_MODIFIED").unwrap_or(created_at),
+        last_visited_at: timestamp("LAST_VISIT"),
+        deleted_at: None,
+        collection: None,
+        tags,
+    })
+}
+
+/// Keys are uppercased, values are decoded
+pub fn parse_attributes(mut attributes: &str) -> HashMap<String, String> {
+    let mut parsed = HashMap::new();
+
+    loop {
+        attributes = attributes.trim_start();
+        let Some(equals) = attributes.find('=') else {
+            break;
+        };
+        let key = attributes[..equals].trim().to_ascii_uppercase();
+        attributes = attributes[equals + 1..].trim_start();
+
+        let value = match attributes.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                attributes = quoted.get(end + 1..).unwrap_or_default();
+                &quoted[..end]
+            }
+            None => {
+                let end = attributes
+                    .find(char::is_whitespace)
+                    .unwrap_or(attributes.len());
+                let value = &attributes[..end];
+                attributes = &attributes[end..];
+                value
+            }
+        };
+
+        parsed.insert(key, decode(value));
+    }
+
+    parsed
+}
+
+/// File with the folders and bookmarks of `root`, see [`crate::export::folders`]
+pub fn write(root: &Folder) -> String {
+    let mut file_data = String::new();
+    file_data.push_str(DOCTYPE);
+    file_data.push_str(concat!(
+        "\n<!-- This is an automatically generated file.\n",
+        "     It will be read and overwritten.\n",
+        "     DO NOT EDIT! -->\n",
+        "<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n",
+        "<TITLE>Bookmarks</TITLE>\n",
+        "<H1>Bookmarks</H1>\n",
+        "<DL><p>\n",
+    ));
+
+    write_folder(&mut file_data, root, "    ");
+
+    file_data.push_str("</DL><p>\n");
+    file_data
+}
+
+/// Folders first, then bookmarks, like browsers write them
+fn write_folder(file_data: &mut String, folder: &Folder, indent: &str) {
+    for child in folder.folders.iter() {
+        let _ = writeln!(file_data, "{indent}<DT><H3>{}</H3>", encode(child.name));
+        let _ = writeln!(file_data, "{indent}<DL><p>");
+        write_folder(file_data, child, &format!("{indent}    "));
+        let _ = writeln!(file_data, "{indent}</DL><p>");
+    }
+    for bookmark in folder.bookmarks.iter() {
+        write_bookmark(file_data, bookmark, indent);
+    }
+}
+
+fn write_bookmark(file_data: &mut String, bookmark: &Bookmark, indent: &str) {
+    let _ = write!(
+        file_data,
+        "{indent}<DT><A HREF=\"{}\"",
+        encode(&bookmark.link)
+    );
+
+    let timestamps = [
+        ("ADD_DATE", Some(bookmark.created_at)),
+        ("LAST_MODIFIED", Some(bookmark.updated_at)),
+        ("LAST_VISIT", bookmark.last_visited_at),
+    ];
+    for (key, timestamp) in timestamps {
+        if let Some(timestamp) = timestamp.filter(|x| *x != 0) {
+            let _ = write!(file_data, " {key}=\"{}\"", timestamp / SECOND);
+        }
+    }
+    if !bookmark.tags.is_empty() {
+        let _ = write!(file_data, " TAGS=\"{}\"", encode(&bookmark.tags.join(",")));
+    }
+
+    let _ = writeln!(file_data, ">{}</A>", encode(&bookmark.title));
+
+    if !bookmark.note.is_empty() {
+        let _ = writeln!(file_data, "{indent}<DD>{}", encode(&bookmark.note));
+    }
+}
+
+/// Escapes what would be read as markup, in text and in attribute values
+pub fn encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => encoded.push_str("&amp;"),
+            '<' => encoded.push_str("&lt;"),
+            '>' => encoded.push_str("&gt;"),
+            '"' => encoded.push_str("&quot;"),
+            character => encoded.push(character),
+        }
+    }
+    encoded
+}
+
+/// Replaces character references, unknown ones are kept as they are
+pub fn decode(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let character = rest.find(';').and_then(|end| {
+            let character = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" | "#39" => '\'',
+                "nbsp" => '\u{a0}',
+                reference => {
+                    let code = match reference
+                        .strip_prefix("#x")
+                        .or(reference.strip_prefix("#X"))
+                    {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => reference.strip_prefix('#').and_then(|x| x.parse().ok()),
+                    };
+                    code.and_then(char::from_u32)?
+                }
+            };
+            Some((character, end))
+        });
+
+        match character {
+            Some((character, end)) => {
+                decoded.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, is_netscape, parse, write};
+    use crate::{
+        export::folders,
+        settings::Settings,
+        store::{Bookmark, Collection},
+    };
+
+    const CHROME: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file.
+     It will be read and overwritten.
+     DO NOT EDIT! -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1717243200" LAST_MODIFIED="1717243300" PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><A HREF="https://www.rust-lang.org/" ADD_DATE="1717243200" ICON="data:image/png;base64,AAAA">Rust</A>
+        <DT><H3 ADD_DATE="1717243200">Web Dev</H3>
+        <DL><p>
+            <DT><A HREF="https://dioxuslabs.com" ADD_DATE="1717250400" LAST_MODIFIED="1717250500" TAGS="rust,UI">Dioxus &amp; friends</A>
+            <DD>Fullstack &lt;GUI&gt; library
+            <DT><A HREF="javascript:alert(1)">Bookmarklet</A>
+        </DL><p>
+    </DL><p>
+    <DT><A HREF="http://example.com" LAST_VISIT="1717416000"></A>
+</DL><p>
+"#;
+
+    #[test]
+    fn detects_doctype() {
+        assert!(is_netscape(CHROME));
+        assert!(is_netscape("\n  <!doctype netscape-bookmark-file-1>"));
+        assert!(!is_netscape(r#"{ "version": 1 }"#));
+    }
+
+    #[test]
+    fn parses_bookmarks() {
+        let bookmarks = parse(CHROME, &Settings::default().allowed_schemes);
+        assert_eq!(bookmarks.len(), 3);
+
+        let rust = &bookmarks[0];
+        assert_eq!(&*rust.title, "Rust");
+        assert_eq!(&*rust.link, "https://www.rust-lang.org/");
+        assert_eq!(rust.created_at, 1717243200000);
+        assert_eq!(rust.updated_at, 1717243200000);
+        assert!(rust.tags.is_empty());
+
+        let dioxus = &bookmarks[1];
+        assert_eq!(&*dioxus.title, "Dioxus & friends");
+        assert_eq!(&*dioxus.note, "Fullstack <GUI> library");
+        assert_eq!(dioxus.updated_at, 1717250500000);
+        let tags: Vec<&str> = dioxus.tags.iter().map(|x| &**x).collect();
+        assert_eq!(tags, vec!["rust", "ui", "web-dev"]);
+
+        let example = &bookmarks[2];
+        assert_eq!(&*example.title, "http://example.com");
+        assert_eq!(example.created_at, 0);
+        assert_eq!(example.last_visited_at, Some(1717416000000));
+        assert!(example.tags.is_empty());
+        assert_eq!(&*example.note, "");
+    }
+
+    #[test]
+    fn decodes_references() {
+        assert_eq!(decode("a &amp; b &#39;c&#x27; &#169;"), "a & b 'c' ©");
+        assert_eq!(decode("AT&T &unknown; &"), "AT&T &unknown; &");
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut bookmarks = vec![
+            Bookmark::new("Dioxus", "https://dioxuslabs.com/", "", 1717243200000)
+                .with_tags(&["rust", "ui"]),
+            Bookmark::new(
+                "\"Rust\" <lang>",
+                "https://www.rust-lang.org/?a=1&b=2",
+                "A & B",
+                1717243200000,
+            )
+            .with_tags(&["rust"]),
+            Bookmark::new("Example", "http://example.com/", "", 1717243200000),
+        ];
+        for bookmark in bookmarks.iter_mut() {
+            bookmark.updated_at = 1717329600000;
+        }
+        bookmarks[2].last_visited_at = Some(1717416000000);
+
+        let file_data = write(&folders(&bookmarks.iter().collect::<Vec<_>>(), &[]));
+        assert!(is_netscape(&file_data));
+
+        let mut read = parse(&file_data, &Settings::default().allowed_schemes);
+        for (read, bookmark) in read.iter_mut().zip(bookmarks.iter()) {
+            read.id = bookmark.id;
+        }
+        assert_eq!(read, bookmarks);
+    }
+
+    #[test]
+    fn writes_collections_as_nested_folders() {
+        let languages = Collection::new("Languages", None, 0);
+        let web = Collection::new("Web & UI", Some(languages.id), 0);
+        let dioxus = Bookmark {
+            collection: Some(web.id),
+            ..Bookmark::new("Dioxus", "https://dioxuslabs.com", "", 1717243200000)
+        };
+
+        let file_data = write(&folders(&[&dioxus], &[&languages, &web]));
+
+        assert!(file_data.ends_with(concat!(
+            "<DL><p>\n",
+            "    <DT><H3>Languages</H3>\n",
+            "    <DL><p>\n",
+            "        <DT><H3>Web &amp; UI</H3>\n",
+            "        <DL><p>\n",
+            "            <DT><A HREF=\"https://dioxuslabs.com\" ADD_DATE=\"1717243200\" LAST_MODIFIED=\"1717243200\">Dioxus</A>\n",
+            "        </DL><p>\n",
+            "    </DL><p>\n",
+            "</DL><p>\n",
+        )));
+        // Browsers don't know collections, reading the file back gives tags
+        let tags: Vec<String> = parse(&file_data, &Settings::default().allowed_schemes)[0]
+            .tags
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        assert_eq!(tags, ["languages", "web-&-ui"]);
+    }
+}
@@ -0,0 +1,132 @@
+use url::Url;
+
+use crate::store::Bookmark;
+
+/// Lowercased alphanumeric runs of `text`
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_lowercase())
+}
+
+/// Host in Unicode and path of a link, scheme, port, query and fragment are dropped. Links
+/// without a host, like `mailto:` and `file:` ones, have their address or path as the host.
+fn split_link(link: &str) -> (String, String) {
+    let Ok(url) = Url::parse(link) else {
+        // Not a URL, like a link saved without a scheme
+        let link = link.split(['?', '#']).next().unwrap_or_default();
+        let (host, path) = link.split_at(link.find('/').unwrap_or(link.len()));
+        // Port isn't meaningful to search or sort by
+        let host = host.split(':').next().unwrap_or_default();
+        return (host.to_owned(), path.to_owned());
+    };
+
+    match url.host_str().filter(|x| !x.is_empty()) {
+        Some(host) => {
+            let (unicode, result) = idna::domain_to_unicode(host);
+            let host = if result.is_ok() {
+                unicode
+            } else {
+                host.to_owned()
+            };
+            (host, url.path().to_owned())
+        }
+        None => (url.path().to_owned(), String::new()),
+    }
+}
+
+/// Lowercased host without `www.`
+pub fn domain(link: &str) -> String {
+    let (host, _) = split_link(link);
+    let host = host.to_lowercase();
+
+    match host.strip_prefix("www.") {
+        Some(host) => host.to_owned(),
+        None => host,
+    }
+}
+
+/// Tokens of host and path like [`tokenize`] makes them, without `www`. Scheme, query and
+/// fragment are ignored.
+pub fn tokenize_link(link: &str) -> Vec<String> {
+    let (host, path) = split_link(link);
+
+    tokenize(&host)
+        .filter(|x| x != "www")
+        .chain(tokenize(&path))
+        .collect()
+}
+
+/// Unique terms of title, link and note
+pub fn bookmark_terms(bookmark: &Bookmark) -> Vec<String> {
+    let mut terms: Vec<String> = tokenize(&bookmark.title)
+        .chain(tokenize_link(&bookmark.link))
+        .chain(tokenize(&bookmark.note))
+        .collect();
+
+    terms.sort_unstable();
+    terms.dedup();
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bookmark_terms, domain, split_link, tokenize, tokenize_link};
+    use crate::store::Bookmark;
+
+    #[test]
+    fn tokenizes_alphanumeric_runs() {
+        assert_eq!(
+            tokenize("Rust's  async-book, v2 Ünïcode").collect::<Vec<_>>(),
+            vec!["rust", "s", "async", "book", "v2", "ünïcode"]
+        );
+        assert_eq!(tokenize(" -- ").count(), 0);
+    }
+
+    #[test]
+    fn splits_links() {
+        let split = |link| {
+            let (host, path) = split_link(link);
+            [host, path]
+        };
+        assert_eq!(
+            split("https://www.rust-lang.org:8080/learn/get-started?a=1#install"),
+            ["www.rust-lang.org", "/learn/get-started"]
+        );
+        assert_eq!(split("https://xn--bcher-kva.de/a"), ["bücher.de", "/a"]);
+        assert_eq!(split("example.com#top"), ["example.com", ""]);
+        assert_eq!(split("mailto:me@example.com"), ["me@example.com", ""]);
+        assert_eq!(
+            split("file:///home/me/notes.txt"),
+            ["/home/me/notes.txt", ""]
+        );
+        assert_eq!(domain("https://WWW.Rust-Lang.org/"), "rust-lang.org");
+        assert_eq!(domain("mailto:Me@Example.com"), "me@example.com");
+    }
+
+    #[test]
+    fn tokenizes_links_without_scheme_and_www() {
+        assert_eq!(
+            tokenize_link("https://www.rust-lang.org/Learn/get_started?q=book#install"),
+            vec!["rust", "lang", "org", "learn", "get", "started"]
+        );
+        assert_eq!(
+            tokenize_link("mailto:me@example.com"),
+            vec!["me", "example", "com"]
+        );
+    }
+
+    #[test]
+    fn collects_unique_terms() {
+        let bookmark = Bookmark::new(
+            "Rust book",
+            "https://doc.rust-lang.org/book/",
+            "The book about Rust",
+            0,
+        );
+        assert_eq!(
+            bookmark_terms(&bookmark),
+            vec!["about", "book", "doc", "lang", "org", "rust", "the"]
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::time::{Timestamp, DAY};
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Days a deleted bookmark stays in trash before it is purged
+    pub trash_retention_days: u32,
+    /// Writes changes to the synced file in the background
+    pub autosave: bool,
+    /// Schemes links can be saved with, lowercase, see [`crate::link::parse`]
+    pub allowed_schemes: Vec<String>,
+}
+
+impl Settings {
+    pub const KEY: &'static str = "settings";
+
+    pub fn trash_retention(&self) -> Timestamp {
+        self.trash_retention_days as Timestamp * DAY
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            trash_retention_days: 30,
+            autosave: false,
+            allowed_schemes: ["http", "https", "ftp", "file", "mailto"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        }
+    }
+}
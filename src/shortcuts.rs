@@ -0,0 +1,45 @@
+//! Keyboard shortcuts that work anywhere on the page, not only in a focused element
+
+use dioxus::prelude::*;
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{HtmlElement, KeyboardEvent};
+
+use crate::Action;
+
+/// `Ctrl+Z` undoes and `Ctrl+Shift+Z` redoes, `Cmd` instead of `Ctrl` on macOS. Text fields keep
+/// their own undo.
+pub fn use_undo_shortcuts(coroutine: Coroutine<Action>) {
+    use_hook(move || {
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+
+        let listener = Closure::<dyn FnMut(KeyboardEvent)>::new(move |ev: KeyboardEvent| {
+            if !(ev.ctrl_key() || ev.meta_key()) || !ev.key().eq_ignore_ascii_case("z") {
+                return;
+            }
+            let is_editing = ev
+                .target()
+                .and_then(|x| x.dyn_into::<HtmlElement>().ok())
+                .is_some_and(|x| {
+                    matches!(x.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+                        || x.is_content_editable()
+                });
+            if is_editing {
+                return;
+            }
+
+            ev.prevent_default();
+            coroutine.send(if ev.shift_key() {
+                Action::Redo
+            } else {
+                Action::Undo
+            });
+        });
+
+        // The listener lives as long as the app
+        let _ =
+            window.add_event_listener_with_callback("keydown", listener.as_ref().unchecked_ref());
+        listener.forget();
+    });
+}
@@ -0,0 +1,67 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+#[component]
+pub fn TagList(
+    tags: Vec<(Rc<str>, usize)>,
+    query: String,
+    error: Option<String>,
+    onquery: EventHandler<String>,
+) -> Element {
+    let item_class = |is_selected: bool| {
+        if is_selected {
+            "flex justify-between px-4 py-1 rounded-md cursor-pointer select-none bg-tertiary font-bold"
+        } else {
+            "flex justify-between px-4 py-1 rounded-md cursor-pointer select-none hover:bg-tertiary"
+        }
+    };
+
+    rsx! {
+        div {
+            class: "w-56 bg-primary border-r border-gray-200",
+            div {
+                class: "sticky top-0 p-4 flex flex-col gap-1",
+                h2 {
+                    class: "text-xl font-bold px-4 pb-4",
+                    "Tags"
+                }
+                input {
+                    class: "bg-primary px-4 h-8 rounded-md border border-gray-300",
+                    placeholder: "rust AND NOT archived",
+                    value: "{query}",
+                    oninput: move |ev| onquery.call(ev.value()),
+                }
+                if let Some(error) = error {
+                    span {
+                        class: "px-4 text-xs text-red-500",
+                        "{error}"
+                    }
+                }
+                div {
+                    class: item_class(query.trim().is_empty()),
+                    onclick: move |_| onquery.call(String::new()),
+                    span { "All" }
+                }
+                for (name, count) in tags {
+                    div {
+                        key: "{name}",
+                        class: item_class(query.trim() == &*name),
+                        onclick: {
+                            let name = name.to_string();
+                            move |_| onquery.call(name.clone())
+                        },
+                        span {
+                            class: "truncate",
+                            "{name}"
+                        }
+                        span {
+                            class: "text-gray-500",
+                            "{count}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Where bookmarks, settings and the file handle are persisted. The app talks to a
+//! [`StorageBackend`], so persistence works the same against IndexedDB in the browser and against
+//! memory or a file in tests.
+
+use futures_util::future::LocalBoxFuture;
+use web_sys::FileSystemFileHandle;
+
+use crate::{
+    library::{Libraries, LibraryId},
+    local_file::FileSnapshot,
+    settings::Settings,
+    store::{Bookmark, BookmarkId, Collection, CollectionId, HistoryEntry},
+};
+
+pub use errors::*;
+pub use file::FileStorage;
+pub use indexed_db::IndexedDb;
+pub use memory::MemoryStorage;
+
+mod file;
+mod indexed_db;
+mod memory;
+
+mod errors {
+    use crate::database::DatabaseError;
+
+    #[derive(Debug)]
+    pub enum StorageError {
+        Database(DatabaseError),
+        Idb(idb::Error),
+        Serde(serde_wasm_bindgen::Error),
+        Json(serde_json::Error),
+        Io(std::io::Error),
+    }
+
+    impl std::error::Error for StorageError {}
+    impl std::fmt::Display for StorageError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::Database(err) => write!(f, "{}", err),
+                Self::Idb(err) => write!(f, "IndexedDB error: {}", err),
+                Self::Serde(err) => write!(f, "Serialization error: {}", err),
+                Self::Json(err) => write!(f, "JSON error: {}", err),
+                Self::Io(err) => write!(f, "IO error: {}", err),
+            }
+        }
+    }
+    impl From<DatabaseError> for StorageError {
+        fn from(err: DatabaseError) -> Self {
+            Self::Database(err)
+        }
+    }
+    impl From<idb::Error> for StorageError {
+        fn from(err: idb::Error) -> Self {
+            Self::Idb(err)
+        }
+    }
+    impl From<serde_wasm_bindgen::Error> for StorageError {
+        fn from(err: serde_wasm_bindgen::Error) -> Self {
+            Self::Serde(err)
+        }
+    }
+    impl From<serde_json::Error> for StorageError {
+        fn from(err: serde_json::Error) -> Self {
+            Self::Json(err)
+        }
+    }
+    impl From<std::io::Error> for StorageError {
+        fn from(err: std::io::Error) -> Self {
+            Self::Io(err)
+        }
+    }
+}
+
+/// Everything a backend has stored
+#[derive(Default)]
+pub struct Stored {
+    pub bookmarks: Vec<Bookmark>,
+    pub collections: Vec<Collection>,
+    /// File that sync writes to
+    pub handle: Option<FileSystemFileHandle>,
+    /// What sync last wrote to the file, the base when merging changes made to it elsewhere
+    pub sync_base: Option<FileSnapshot>,
+    pub settings: Settings,
+    /// Only kept in the default library's storage
+    pub libraries: Libraries,
+}
+
+pub enum Write {
+    /// Keyed by [`Bookmark::id`]
+    PutBookmark(Bookmark),
+    DeleteBookmark(BookmarkId),
+    /// Keyed by [`Collection::id`]
+    PutCollection(Collection),
+    DeleteCollection(CollectionId),
+    /// Appended, entries are never changed
+    AddHistory(HistoryEntry),
+    /// Every entry of the bookmark
+    DeleteHistory(BookmarkId),
+    PutHandle(FileSystemFileHandle),
+    PutSyncBase(FileSnapshot),
+    PutSettings(Settings),
+    PutLibraries(Libraries),
+}
+
+pub trait StorageBackend {
+    fn load(&self) -> LocalBoxFuture<'_, Result<Stored, StorageError>>;
+    /// Applies either all of the writes or none of them
+    fn transaction(&self, writes: Vec<Write>) -> LocalBoxFuture<'_, Result<(), StorageError>>;
+    /// Entries of the bookmark in the order they were added, see [`HistoryEntry`]
+    fn history(
+        &self,
+        id: BookmarkId,
+    ) -> LocalBoxFuture<'_, Result<Vec<HistoryEntry>, StorageError>>;
+
+    fn put_bookmark(&self, bookmark: Bookmark) -> LocalBoxFuture<'_, Result<(), StorageError>> {
+        self.transaction(vec![Write::PutBookmark(bookmark)])
+    }
+    fn delete_bookmark(&self, id: BookmarkId) -> LocalBoxFuture<'_, Result<(), StorageError>> {
+        self.transaction(vec![Write::DeleteBookmark(id)])
+    }
+    fn put_handle(
+        &self,
+        handle: FileSystemFileHandle,
+    ) -> LocalBoxFuture<'_, Result<(), StorageError>> {
+        self.transaction(vec![Write::PutHandle(handle)])
+    }
+    fn put_settings(&self, settings: Settings) -> LocalBoxFuture<'_, Result<(), StorageError>> {
+        self.transaction(vec![Write::PutSettings(settings)])
+    }
+    fn put_libraries(&self, libraries: Libraries) -> LocalBoxFuture<'_, Result<(), StorageError>> {
+        self.transaction(vec![Write::PutLibraries(libraries)])
+    }
+}
+
+/// The library's IndexedDB database in the browser, a file next to the executable elsewhere
+pub async fn open(library: LibraryId) -> Result<Box<dyn StorageBackend>, StorageError> {
+    let name = library.storage_name();
+
+    if cfg!(target_arch = "wasm32") {
+        Ok(Box::new(IndexedDb::open(&name).await?))
+    } else {
+        Ok(Box::new(FileStorage::new(format!("{name}.json"))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::{FileStorage, MemoryStorage, StorageBackend, Write};
+    use crate::{
+        library::Libraries,
+        local_file::FileSnapshot,
+        settings::Settings,
+        store::{Bookmark, BookmarkId, Collection, CollectionId, HistoryEntry},
+    };
+
+    /// Memory and file backends never wait, so their futures are ready on the first poll
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut context = Context::from_waker(Waker::noop());
+        match pin!(future).poll(&mut context) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("storage future should be ready"),
+        }
+    }
+
+    fn round_trips(backend: &dyn StorageBackend) {
+        let stored = block_on(backend.load()).unwrap();
+        assert!(stored.bookmarks.is_empty());
+        assert!(stored.collections.is_empty());
+        assert_eq!(stored.sync_base, None);
+        assert_eq!(stored.settings, Settings::default());
+        assert_eq!(stored.libraries, Libraries::default());
+
+        let mut libraries = Libraries::default();
+        libraries.current = libraries.create("Work");
+
+        let sync_base = FileSnapshot {
+            file_data: "{}".to_string(),
+            last_modified: 1,
+        };
+
+        let a = Bookmark::new("a", "https://a.com", "", 0).with_tags(&["rust"]);
+        let b = Bookmark::new("b", "https://b.com", "", 0).with_tags(&["rust"]);
+        // Same bookmark as a, changed
+        let c = Bookmark {
+            id: a.id,
+            ..Bookmark::new("c", "https://c.com", "", 0).with_tags(&["rust"])
+        };
+        let collection = |name: &str| Collection {
+            id: CollectionId::new(),
+            name: name.into(),
+            parent: None,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let (work, archive) = (collection("Work"), collection("Archive"));
+        let entry = |id: BookmarkId, changed_at| HistoryEntry {
+            bookmark: id,
+            changed_at,
+            title: "old".into(),
+            link: "https://old.com".into(),
+            note: "".into(),
+        };
+
+        block_on(backend.transaction(vec![
+            Write::PutBookmark(a.clone()),
+            Write::PutBookmark(b.clone()),
+            Write::PutCollection(work.clone()),
+            Write::PutCollection(archive.clone()),
+            Write::PutSettings(Settings {
+                trash_retention_days: 7,
+                ..Settings::default()
+            }),
+            Write::PutSyncBase(sync_base.clone()),
+            Write::PutLibraries(libraries.clone()),
+            Write::AddHistory(entry(a.id, 1)),
+            Write::AddHistory(entry(b.id, 1)),
+        ]))
+        .unwrap();
+        block_on(backend.transaction(vec![Write::AddHistory(entry(a.id, 2))])).unwrap();
+        block_on(backend.put_bookmark(c.clone())).unwrap();
+
+        let mut stored = block_on(backend.load()).unwrap();
+        stored.bookmarks.sort_by_key(|x| x.title.clone());
+        assert_eq!(stored.bookmarks, vec![b.clone(), c]);
+        assert_eq!(stored.settings.trash_retention_days, 7);
+        assert_eq!(stored.sync_base, Some(sync_base));
+        assert_eq!(stored.libraries, libraries);
+        stored.collections.sort_by_key(|x| x.name.clone());
+        assert_eq!(stored.collections, vec![archive.clone(), work.clone()]);
+        assert_eq!(
+            block_on(backend.history(a.id)).unwrap(),
+            vec![entry(a.id, 1), entry(a.id, 2)]
+        );
+
+        block_on(backend.transaction(vec![
+            Write::DeleteBookmark(a.id),
+            Write::DeleteCollection(archive.id),
+            Write::DeleteHistory(a.id),
+        ]))
+        .unwrap();
+
+        let stored = block_on(backend.load()).unwrap();
+        assert_eq!(stored.bookmarks, vec![b.clone()]);
+        assert_eq!(stored.collections, vec![work]);
+        assert!(block_on(backend.history(a.id)).unwrap().is_empty());
+        assert_eq!(
+            block_on(backend.history(b.id)).unwrap(),
+            vec![entry(b.id, 1)]
+        );
+    }
+
+    #[test]
+    fn memory_round_trips() {
+        round_trips(&MemoryStorage::default());
+    }
+
+    #[test]
+    fn file_round_trips() {
+        let path = std::env::temp_dir().join(format!("librarian-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        round_trips(&FileStorage::new(&path));
+
+        // Everything is in the file, not in the backend
+        let stored = block_on(FileStorage::new(&path).load()).unwrap();
+        assert_eq!(stored.bookmarks.len(), 1);
+        assert_eq!(&*stored.bookmarks[0].title, "b");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use futures_util::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+use web_sys::FileSystemFileHandle;
+
+use super::{StorageBackend, StorageError, Stored, Write};
+use crate::{
+    library::Libraries,
+    local_file::FileSnapshot,
+    settings::Settings,
+    store::{Bookmark, BookmarkId, Collection, CollectionId, HistoryEntry},
+};
+
+/// Everything in a single JSON file, rewritten on every transaction
+pub struct FileStorage {
+    path: PathBuf,
+    /// Handles are browser objects, they can't be written to a file
+    handle: RefCell<Option<FileSystemFileHandle>>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StorageFile {
+    #[serde(default)]
+    bookmarks: BTreeMap<BookmarkId, Bookmark>,
+    #[serde(default)]
+    collections: BTreeMap<CollectionId, Collection>,
+    #[serde(default)]
+    history: Vec<HistoryEntry>,
+    #[serde(default)]
+    sync_base: Option<FileSnapshot>,
+    #[serde(default)]
+    settings: Settings,
+    #[serde(default)]
+    libraries: Libraries,
+}
+
+impl FileStorage {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            handle: RefCell::new(None),
+        }
+    }
+
+    fn read(&self) -> Result<StorageFile, StorageError> {
+        match fs::read_to_string(&self.path) {
+            Ok(data) => Ok(serde_json::from_str(&data)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(StorageFile::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes next to the file first, so a failed write leaves the old file intact
+    fn write(&self, file: &StorageFile) -> Result<(), StorageError> {
+        let temp_path = self.path.with_extension("json.tmp");
+
+        fs::write(&temp_path, serde_json::to_string(file)?)?;
+        fs::rename(&temp_path, &self.path)?;
+
+        Ok(())
+    }
+}
+
+impl StorageBackend for FileStorage {
+    fn load(&self) -> LocalBoxFuture<'_, Result<Stored, StorageError>> {
+        Box::pin(async move {
+            let file = self.read()?;
+
+            Ok(Stored {
+                bookmarks: file.bookmarks.into_values().collect(),
+                collections: file.collections.into_values().collect(),
+                handle: self.handle.borrow().clone(),
+                sync_base: file.sync_base,
+                settings: file.settings,
+                libraries: file.libraries,
+            })
+        })
+    }
+
+    fn transaction(&self, writes: Vec<Write>) -> LocalBoxFuture<'_, Result<(), StorageError>> {
+        Box::pin(async move {
+            let mut file = self.read()?;
+            let mut handle = None;
+
+            for write in writes {
+                match write {
+                    Write::PutBookmark(bookmark) => {
+                        file.bookmarks.insert(bookmark.id, bookmark);
+                    }
+                    Write::DeleteBookmark(id) => {
+                        file.bookmarks.remove(&id);
+                    }
+                    Write::PutCollection(collection) => {
+                        file.collections.insert(collection.id, collection);
+                    }
+                    Write::DeleteCollection(id) => {
+                        file.collections.remove(&id);
+                    }
+                    Write::AddHistory(entry) => file.history.push(entry),
+                    Write::DeleteHistory(id) => file.history.retain(|x| x.bookmark != id),
+                    Write::PutHandle(new_handle) => handle = Some(new_handle),
+                    Write::PutSyncBase(sync_base) => file.sync_base = Some(sync_base),
+                    Write::PutSettings(settings) => file.settings = settings,
+                    Write::PutLibraries(libraries) => file.libraries = libraries,
+                }
+            }
+
+            self.write(&file)?;
+
+            if handle.is_some() {
+                *self.handle.borrow_mut() = handle;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn history(
+        &self,
+        id: BookmarkId,
+    ) -> LocalBoxFuture<'_, Result<Vec<HistoryEntry>, StorageError>> {
+        Box::pin(async move {
+            let file = self.read()?;
+
+            Ok(file
+                .history
+                .into_iter()
+                .filter(|x| x.bookmark == id)
+                .collect())
+        })
+    }
+}
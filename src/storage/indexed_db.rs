@@ -0,0 +1,226 @@
+use futures_util::future::LocalBoxFuture;
+use idb::{Database, Query, TransactionMode};
+use serde::Serialize;
+use serde_wasm_bindgen::Serializer;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::FileSystemFileHandle;
+
+use super::{StorageBackend, StorageError, Stored, Write};
+use crate::{
+    database,
+    library::Libraries,
+    local_file::FileSnapshot,
+    settings::Settings,
+    store::{Bookmark, BookmarkId, Collection, HistoryEntry},
+};
+
+/// Key of the handle that sync writes to in `handles` store
+const INITIAL_HANDLE: &str = "initial_file";
+/// Key of what sync last wrote to that file in `handles` store
+const SYNC_BASE: &str = "initial_file_base";
+
+pub struct IndexedDb {
+    database: Database,
+}
+
+impl IndexedDb {
+    pub async fn open(name: &str) -> Result<Self, StorageError> {
+        Ok(Self {
+            database: database::open(name).await?,
+        })
+    }
+}
+
+impl Write {
+    fn store_name(&self) -> &'static str {
+        match self {
+            Self::PutBookmark(_) | Self::DeleteBookmark(_) => "bookmarks",
+            Self::PutCollection(_) | Self::DeleteCollection(_) => "collections",
+            Self::AddHistory(_) | Self::DeleteHistory(_) => "history",
+            Self::PutHandle(_) | Self::PutSyncBase(_) => "handles",
+            Self::PutSettings(_) | Self::PutLibraries(_) => "settings",
+        }
+    }
+}
+
+impl StorageBackend for IndexedDb {
+    fn load(&self) -> LocalBoxFuture<'_, Result<Stored, StorageError>> {
+        Box::pin(async move {
+            let transaction = self.database.transaction(
+                &["bookmarks", "collections", "handles", "settings"],
+                TransactionMode::ReadOnly,
+            )?;
+
+            let bookmarks_os = transaction.object_store("bookmarks")?;
+            let bookmarks = bookmarks_os
+                .get_all(None, None)?
+                .await?
+                .into_iter()
+                .map(serde_wasm_bindgen::from_value)
+                .collect::<Result<Vec<Bookmark>, _>>()?;
+
+            let collections_os = transaction.object_store("collections")?;
+            let collections = collections_os
+                .get_all(None, None)?
+                .await?
+                .into_iter()
+                .map(serde_wasm_bindgen::from_value)
+                .collect::<Result<Vec<Collection>, _>>()?;
+
+            let handles_os = transaction.object_store("handles")?;
+            let handle = handles_os
+                .get(JsValue::from_str(INITIAL_HANDLE))?
+                .await?
+                .and_then(|x| x.dyn_into::<FileSystemFileHandle>().ok());
+            let sync_base = match handles_os.get(JsValue::from_str(SYNC_BASE))?.await? {
+                Some(sync_base) => Some(serde_wasm_bindgen::from_value::<FileSnapshot>(sync_base)?),
+                None => None,
+            };
+
+            let settings_os = transaction.object_store("settings")?;
+            let settings = match settings_os.get(JsValue::from_str(Settings::KEY))?.await? {
+                Some(settings) => serde_wasm_bindgen::from_value(settings)?,
+                None => Settings::default(),
+            };
+            let libraries = match settings_os.get(JsValue::from_str(Libraries::KEY))?.await? {
+                Some(libraries) => serde_wasm_bindgen::from_value(libraries)?,
+                None => Libraries::default(),
+            };
+
+            transaction.await?;
+
+            Ok(Stored {
+                bookmarks,
+                collections,
+                handle,
+                sync_base,
+                settings,
+                libraries,
+            })
+        })
+    }
+
+    fn transaction(&self, writes: Vec<Write>) -> LocalBoxFuture<'_, Result<(), StorageError>> {
+        Box::pin(async move {
+            if writes.is_empty() {
+                return Ok(());
+            }
+
+            let serializer = Serializer::json_compatible();
+
+            let mut store_names: Vec<_> = writes.iter().map(Write::store_name).collect();
+            store_names.sort_unstable();
+            store_names.dedup();
+
+            let transaction = self
+                .database
+                .transaction(&store_names, TransactionMode::ReadWrite)?;
+
+            let result = async {
+                for write in writes {
+                    let object_store = transaction.object_store(write.store_name())?;
+
+                    match write {
+                        Write::PutBookmark(bookmark) => {
+                            let value = bookmark.serialize(&serializer)?;
+                            object_store
+                                .put(&value, Some(&JsValue::from_str(&bookmark.id.to_string())))?
+                                .await?;
+                        }
+                        Write::DeleteBookmark(id) => {
+                            object_store
+                                .delete(JsValue::from_str(&id.to_string()))?
+                                .await?;
+                        }
+                        Write::PutCollection(collection) => {
+                            let value = collection.serialize(&serializer)?;
+                            object_store
+                                .put(&value, Some(&JsValue::from_str(&collection.id.to_string())))?
+                                .await?;
+                        }
+                        Write::DeleteCollection(id) => {
+                            object_store
+                                .delete(JsValue::from_str(&id.to_string()))?
+                                .await?;
+                        }
+                        Write::AddHistory(entry) => {
+                            // Keyed by auto increment, so entries load in the order they were added
+                            let value = entry.serialize(&serializer)?;
+                            object_store.add(&value, None)?.await?;
+                        }
+                        Write::DeleteHistory(id) => {
+                            let query = Query::from(JsValue::from_str(&id.to_string()));
+                            let keys = object_store
+                                .index("bookmark")?
+                                .get_all_keys(Some(query), None)?
+                                .await?;
+                            for key in keys {
+                                object_store.delete(key)?.await?;
+                            }
+                        }
+                        Write::PutHandle(handle) => {
+                            object_store
+                                .put(&handle, Some(&JsValue::from_str(INITIAL_HANDLE)))?
+                                .await?;
+                        }
+                        Write::PutSyncBase(sync_base) => {
+                            let value = sync_base.serialize(&serializer)?;
+                            object_store
+                                .put(&value, Some(&JsValue::from_str(SYNC_BASE)))?
+                                .await?;
+                        }
+                        Write::PutSettings(settings) => {
+                            let value = settings.serialize(&serializer)?;
+                            object_store
+                                .put(&value, Some(&JsValue::from_str(Settings::KEY)))?
+                                .await?;
+                        }
+                        Write::PutLibraries(libraries) => {
+                            let value = libraries.serialize(&serializer)?;
+                            object_store
+                                .put(&value, Some(&JsValue::from_str(Libraries::KEY)))?
+                                .await?;
+                        }
+                    }
+                }
+                Ok::<_, StorageError>(())
+            }
+            .await;
+
+            // Writes that succeeded would otherwise be committed
+            if let Err(err) = result {
+                let _ = transaction.abort();
+                return Err(err);
+            }
+
+            transaction.await?;
+
+            Ok(())
+        })
+    }
+
+    fn history(
+        &self,
+        id: BookmarkId,
+    ) -> LocalBoxFuture<'_, Result<Vec<HistoryEntry>, StorageError>> {
+        Box::pin(async move {
+            let transaction = self
+                .database
+                .transaction(&["history"], TransactionMode::ReadOnly)?;
+
+            let query = Query::from(JsValue::from_str(&id.to_string()));
+            let entries = transaction
+                .object_store("history")?
+                .index("bookmark")?
+                .get_all(Some(query), None)?
+                .await?
+                .into_iter()
+                .map(serde_wasm_bindgen::from_value)
+                .collect::<Result<Vec<HistoryEntry>, _>>()?;
+
+            transaction.await?;
+
+            Ok(entries)
+        })
+    }
+}
@@ -0,0 +1,88 @@
+use std::{cell::RefCell, collections::BTreeMap};
+
+use futures_util::future::LocalBoxFuture;
+use web_sys::FileSystemFileHandle;
+
+use super::{StorageBackend, StorageError, Stored, Write};
+use crate::{
+    library::Libraries,
+    local_file::FileSnapshot,
+    settings::Settings,
+    store::{Bookmark, BookmarkId, Collection, CollectionId, HistoryEntry},
+};
+
+/// Keeps everything until it is dropped, used when nothing else is available
+#[derive(Default)]
+pub struct MemoryStorage {
+    inner: RefCell<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    bookmarks: BTreeMap<BookmarkId, Bookmark>,
+    collections: BTreeMap<CollectionId, Collection>,
+    history: Vec<HistoryEntry>,
+    handle: Option<FileSystemFileHandle>,
+    sync_base: Option<FileSnapshot>,
+    settings: Settings,
+    libraries: Libraries,
+}
+
+impl StorageBackend for MemoryStorage {
+    fn load(&self) -> LocalBoxFuture<'_, Result<Stored, StorageError>> {
+        let inner = self.inner.borrow();
+        let stored = Stored {
+            bookmarks: inner.bookmarks.values().cloned().collect(),
+            collections: inner.collections.values().cloned().collect(),
+            handle: inner.handle.clone(),
+            sync_base: inner.sync_base.clone(),
+            settings: inner.settings.clone(),
+            libraries: inner.libraries.clone(),
+        };
+
+        Box::pin(async move { Ok(stored) })
+    }
+
+    fn transaction(&self, writes: Vec<Write>) -> LocalBoxFuture<'_, Result<(), StorageError>> {
+        let mut inner = self.inner.borrow_mut();
+        for write in writes {
+            match write {
+                Write::PutBookmark(bookmark) => {
+                    inner.bookmarks.insert(bookmark.id, bookmark);
+                }
+                Write::DeleteBookmark(id) => {
+                    inner.bookmarks.remove(&id);
+                }
+                Write::PutCollection(collection) => {
+                    inner.collections.insert(collection.id, collection);
+                }
+                Write::DeleteCollection(id) => {
+                    inner.collections.remove(&id);
+                }
+                Write::AddHistory(entry) => inner.history.push(entry),
+                Write::DeleteHistory(id) => inner.history.retain(|x| x.bookmark != id),
+                Write::PutHandle(handle) => inner.handle = Some(handle),
+                Write::PutSyncBase(sync_base) => inner.sync_base = Some(sync_base),
+                Write::PutSettings(settings) => inner.settings = settings,
+                Write::PutLibraries(libraries) => inner.libraries = libraries,
+            }
+        }
+
+        Box::pin(async { Ok(()) })
+    }
+
+    fn history(
+        &self,
+        id: BookmarkId,
+    ) -> LocalBoxFuture<'_, Result<Vec<HistoryEntry>, StorageError>> {
+        let inner = self.inner.borrow();
+        let entries = inner
+            .history
+            .iter()
+            .filter(|x| x.bookmark == id)
+            .cloned()
+            .collect();
+
+        Box::pin(async move { Ok(entries) })
+    }
+}
@@ -1,13 +1,33 @@
 use core::hash;
-use std::{collections::HashMap, rc::Rc};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Bound,
+    rc::Rc,
+};
 
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::{
-    arena::{Arena, ArenaId, IterArenaIds},
+    arena::{Arena, ArenaId},
     bit_field::BitField,
+    link, search,
+    time::Timestamp,
 };
 
+pub use collections::{Collection, CollectionId, CollectionNode};
+pub use history::HistoryEntry;
+pub use query::{QueryError, TagQuery};
+pub use transfers::Transfer;
+pub use undo::{Command, UndoStack};
+
+mod collections;
+mod duplicates;
+mod history;
+mod query;
+mod transfers;
+mod undo;
+
 #[derive(Clone)]
 struct Interned {
     rc: Rc<str>,
@@ -35,13 +55,123 @@ impl InternedId {
     pub const DANGLING: InternedId = InternedId { inner: 0 };
 }
 
+/// Identifies a bookmark across reloads and files, unlike its [`ArenaId`] which is only valid
+/// until the store is dropped
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BookmarkId(Uuid);
+
+impl BookmarkId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for BookmarkId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for BookmarkId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Bookmark {
+    pub id: BookmarkId,
     pub title: Rc<str>,
     pub link: Rc<str>,
     pub note: Rc<str>,
+    /// 0 if the bookmark is from before timestamps were kept
+    #[serde(default)]
+    pub created_at: Timestamp,
+    /// Last change of title, link, note, tags or collection
+    #[serde(default)]
+    pub updated_at: Timestamp,
+    /// Last time its link was opened from a card
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_visited_at: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<Timestamp>,
+    /// None if it is not filed in a collection, see [`collections`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<CollectionId>,
+    #[serde(default)]
+    pub tags: Vec<Rc<str>>,
+}
+
+impl Bookmark {
+    /// Not in a store yet, see [`Store::create_bookmark`]
+    pub fn new(title: &str, link: &str, note: &str, now: Timestamp) -> Self {
+        Self {
+            id: BookmarkId::new(),
+            title: Rc::from(title),
+            link: Rc::from(link),
+            note: Rc::from(note),
+            created_at: now,
+            updated_at: now,
+            last_visited_at: None,
+            deleted_at: None,
+            collection: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ImportReport {
+    pub added: usize,
+    pub updated: usize,
+    /// Already in the store with the same data
+    pub skipped: usize,
+}
+
+/// Order of [`Store::search`] results
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SortOrder {
+    #[default]
+    Newest,
+    Oldest,
+    /// Never visited ones last
+    RecentlyVisited,
+    /// Case insensitive
+    Title,
+    /// By [`search::domain`], then title
+    Domain,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 5] = [
+        Self::Newest,
+        Self::Oldest,
+        Self::RecentlyVisited,
+        Self::Title,
+        Self::Domain,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Newest => "Newest",
+            Self::Oldest => "Oldest",
+            Self::RecentlyVisited => "Recently visited",
+            Self::Title => "Title A–Z",
+            Self::Domain => "Domain",
+        }
+    }
+}
+
+/// Trimmed, lowercased and whitespace replaced with `-`, empty if there is nothing left
+pub fn normalize_tag(name: &str) -> String {
+    name.split_whitespace()
+        .map(|x| x.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
 }
 
+/// Bookmarks with the tag, indexed by arena slot. Trash is not counted.
 struct TagContainer {
     tags: BitField,
     tag_count: usize,
@@ -52,9 +182,25 @@ pub struct Store {
     interned: Vec<Rc<str>>,
     reverse_interned: HashMap<String, Interned>,
     bookmarks: Arena<Bookmark>,
-    filtered_items: Vec<Bookmark>,
+    ids: HashMap<BookmarkId, ArenaId<Bookmark>>,
+    filtered_items: Vec<ArenaId<Bookmark>>,
     tags: HashMap<InternedId, TagContainer>,
+    /// Term to bookmarks containing it, sorted so terms with a prefix are next to each other.
+    /// Trash is not indexed.
+    search_index: BTreeMap<String, HashSet<ArenaId<Bookmark>>>,
+    /// Normalized link to bookmarks with it, see [`duplicates`]. Trash is not indexed.
+    links: HashMap<String, HashSet<ArenaId<Bookmark>>>,
+    trash: Vec<ArenaId<Bookmark>>,
     changes: Vec<ArenaId<Bookmark>>,
+    removals: Vec<BookmarkId>,
+    collections: Arena<Collection>,
+    collection_ids: HashMap<CollectionId, ArenaId<Collection>>,
+    collection_changes: Vec<ArenaId<Collection>>,
+    collection_removals: Vec<CollectionId>,
+    /// Entries to persist, see [`HistoryEntry`]
+    history: Vec<HistoryEntry>,
+    /// Writes to other libraries, see [`Transfer`]
+    transfers: Vec<Transfer>,
 }
 
 impl Store {
@@ -83,36 +229,526 @@ impl Store {
         &self.interned[id.inner as usize]
     }
     pub fn create_tag(&mut self, id: InternedId) {
-        let tags = BitField::init(self.bookmarks.len());
-        // Currently overwrites, handle sometime
-        self.tags.insert(id, TagContainer { tags, tag_count: 0 });
+        let len = self.bookmarks.len();
+        self.tags.entry(id).or_insert_with(|| TagContainer {
+            tags: BitField::init(len),
+            tag_count: 0,
+        });
+    }
+    /// Removes the tag from every bookmark, including the ones in trash. Applied as commands, so
+    /// it can be undone.
+    pub fn remove_tag(&self, name: &str) -> Vec<Command> {
+        let tag: Rc<str> = Rc::from(normalize_tag(name));
+        self.bookmarks
+            .iter_ids()
+            .map(|id| self.bookmark(id))
+            .filter(|x| x.tags.contains(&tag))
+            .map(|x| Command::UntagBookmark {
+                id: x.id,
+                tag: tag.clone(),
+            })
+            .collect()
     }
-    pub fn remove_tag(&mut self, id: InternedId) {
-        self.tags.remove(&id);
+    pub fn tag_id(&self, name: &str) -> Option<InternedId> {
+        self.reverse_interned
+            .get(name)
+            .map(|x| x.id)
+            .filter(|id| self.tags.contains_key(id))
     }
+    /// Tags used by at least one bookmark with their counts, sorted by name
+    pub fn tags(&self) -> Vec<(Rc<str>, usize)> {
+        let mut tags: Vec<_> = self
+            .tags
+            .iter()
+            .filter(|(_, container)| container.tag_count > 0)
+            .map(|(id, container)| {
+                (
+                    self.interned[id.inner as usize].clone(),
+                    container.tag_count,
+                )
+            })
+            .collect();
+        tags.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        tags
+    }
+    /// Does nothing if the name is empty after [`normalize_tag`] or the bookmark already has it
+    pub fn tag_bookmark(&mut self, id: ArenaId<Bookmark>, name: &str, now: Timestamp) {
+        let name = normalize_tag(name);
+        if name.is_empty() {
+            return;
+        }
+
+        let tag = self.intern(&name);
+        let rc = self.interned[tag.inner as usize].clone();
+
+        let bookmark = self.bookmarks.entry_mut(id).value;
+        if bookmark.tags.contains(&rc) {
+            return;
+        }
+        bookmark.tags.push(rc);
+        bookmark.updated_at = now;
+        let is_deleted = bookmark.deleted_at.is_some();
+
+        self.create_tag(tag);
+        if !is_deleted {
+            self.set_tag_bit(tag, id, true);
+        }
+        self.changes.push(id);
+    }
+    pub fn untag_bookmark(&mut self, id: ArenaId<Bookmark>, name: &str, now: Timestamp) {
+        let name = normalize_tag(name);
+        let bookmark = self.bookmarks.entry_mut(id).value;
+        let Some(index) = bookmark.tags.iter().position(|x| **x == *name) else {
+            return;
+        };
+        bookmark.tags.remove(index);
+        bookmark.updated_at = now;
+        let is_deleted = bookmark.deleted_at.is_some();
+
+        if let (Some(tag), false) = (self.tag_id(&name), is_deleted) {
+            self.set_tag_bit(tag, id, false);
+        }
+        self.changes.push(id);
+    }
+    fn set_tag_bit(&mut self, tag: InternedId, id: ArenaId<Bookmark>, value: bool) {
+        let Some(container) = self.tags.get_mut(&tag) else {
+            return;
+        };
+        let index = id.id() as usize;
+        if container.tags.get(index) == value {
+            return;
+        }
+        container.tags.set(index, value);
+        if value {
+            container.tag_count += 1;
+        } else {
+            container.tag_count -= 1;
+        }
+    }
+    /// Bookmark's [`BookmarkId`] should not be in the store already, see [`Store::find`]
     pub fn add_bookmark(&mut self, bookmark: Bookmark) -> ArenaId<Bookmark> {
+        let deleted_at = bookmark.deleted_at;
+        let bookmark_id = bookmark.id;
         let id = self.bookmarks.add(bookmark);
+        self.ids.insert(bookmark_id, id);
         self.filtered_items.reserve(1);
+        if deleted_at.is_none() {
+            self.index_bookmark(id);
+        }
+        if let Some(deleted_at) = deleted_at {
+            // Keep trash ordered by deletion time, regardless of load order
+            let bookmarks = &self.bookmarks;
+            let index = self
+                .trash
+                .partition_point(|x| bookmarks.entry(*x).value.deleted_at <= Some(deleted_at));
+            self.trash.insert(index, id);
+        }
         id
     }
-    pub fn create_bookmark(&mut self, title: &str, link: &str, note: &str) -> ArenaId<Bookmark> {
-        let id = self.add_bookmark(Bookmark {
-            title: Rc::from(title),
-            link: Rc::from(link),
-            note: Rc::from(note),
-        });
+    /// Like [`Store::add_bookmark`], but it is a change to persist. A bookmark that was removed
+    /// can be created again with its [`BookmarkId`], see [`Command`].
+    pub fn create_bookmark(&mut self, bookmark: Bookmark) -> ArenaId<Bookmark> {
+        let bookmark_id = bookmark.id;
+        let id = self.add_bookmark(bookmark);
+        self.removals.retain(|x| *x != bookmark_id);
         self.changes.push(id);
 
         id
     }
+    pub fn update_bookmark(
+        &mut self,
+        id: ArenaId<Bookmark>,
+        title: &str,
+        link: &str,
+        note: &str,
+        now: Timestamp,
+    ) {
+        let bookmark = self.bookmark(id);
+        if &*bookmark.title != title || &*bookmark.link != link || &*bookmark.note != note {
+            self.record_history(id, now);
+        }
+
+        let is_indexed = self.bookmarks.entry(id).value.deleted_at.is_none();
+        if is_indexed {
+            self.unindex_bookmark(id);
+        }
+
+        let bookmark = self.bookmarks.entry_mut(id).value;
+        bookmark.title = Rc::from(title);
+        bookmark.link = Rc::from(link);
+        bookmark.note = Rc::from(note);
+        bookmark.updated_at = now;
+        self.changes.push(id);
+
+        if is_indexed {
+            self.index_bookmark(id);
+        }
+    }
+    /// Merges bookmarks by id, then by [`link::normalize`]d link. Existing ones take title and
+    /// note from the imported one and get its tags added, ones matched by link keep their link.
+    /// Imported bookmarks are never put in trash, new ones keep their timestamps. Collections are added first so new bookmarks can be
+    /// filed in them, ones already in the store are kept as they are. Applied as commands, so an
+    /// import can be undone.
+    pub fn import(
+        &self,
+        bookmarks: impl IntoIterator<Item = Bookmark>,
+        collections: impl IntoIterator<Item = Collection>,
+    ) -> (Vec<Command>, ImportReport) {
+        let mut commands: Vec<Command> = collections
+            .into_iter()
+            .filter(|x| self.find_collection(x.id).is_none())
+            .map(Command::CreateCollection)
+            .collect();
+
+        let mut report = ImportReport::default();
+        let mut links: HashMap<String, BookmarkId> = self
+            .all_ids()
+            .map(|id| self.bookmark(id))
+            .map(|x| (link::normalize(&x.link), x.id))
+            .collect();
+        // Bookmarks as they are once the commands are applied
+        let mut merged: HashMap<BookmarkId, Bookmark> = HashMap::new();
+
+        for mut bookmark in bookmarks {
+            bookmark.deleted_at = None;
+            let mut tags: Vec<Rc<str>> = Vec::new();
+            for tag in bookmark.tags.iter().map(|x| normalize_tag(x)) {
+                if !tag.is_empty() && !tags.iter().any(|x| **x == *tag) {
+                    tags.push(Rc::from(tag));
+                }
+            }
+            bookmark.tags = tags;
+
+            let existing_id =
+                if merged.contains_key(&bookmark.id) || self.find(bookmark.id).is_some() {
+                    Some(bookmark.id)
+                } else {
+                    links.get(&link::normalize(&bookmark.link)).copied()
+                };
+            let existing = existing_id.and_then(|id| {
+                merged
+                    .get(&id)
+                    .cloned()
+                    .or_else(|| Some(self.bookmark(self.find(id)?).clone()))
+            });
+            let Some(mut existing) = existing else {
+                links.insert(link::normalize(&bookmark.link), bookmark.id);
+                merged.insert(bookmark.id, bookmark.clone());
+                commands.push(Command::CreateBookmark(bookmark));
+                report.added += 1;
+                continue;
+            };
+            if existing.id != bookmark.id {
+                bookmark.link = existing.link.clone();
+            }
+
+            let is_same = existing.deleted_at.is_none()
+                && existing.link == bookmark.link
+                && existing.title == bookmark.title
+                && existing.note == bookmark.note
+                && bookmark.tags.iter().all(|x| existing.tags.contains(x));
+            if is_same {
+                report.skipped += 1;
+                continue;
+            }
+
+            let id = existing.id;
+            if existing.deleted_at.is_some() {
+                commands.push(Command::RestoreBookmark(id));
+            }
+            links.remove(&link::normalize(&existing.link));
+            links.insert(link::normalize(&bookmark.link), id);
+            commands.push(Command::UpdateBookmark {
+                id,
+                title: bookmark.title.clone(),
+                link: bookmark.link.clone(),
+                note: bookmark.note.clone(),
+            });
+            for tag in bookmark.tags {
+                if !existing.tags.contains(&tag) {
+                    existing.tags.push(tag.clone());
+                    commands.push(Command::TagBookmark { id, tag });
+                }
+            }
+
+            existing.title = bookmark.title;
+            existing.link = bookmark.link;
+            existing.note = bookmark.note;
+            existing.deleted_at = None;
+            merged.insert(id, existing);
+            report.updated += 1;
+        }
+
+        (commands, report)
+    }
+    /// Makes the bookmark with the same [`BookmarkId`] equal to `bookmark`, restoring it from
+    /// trash, or adds it. Applies changes made elsewhere, so timestamps are taken from `bookmark`.
+    pub fn put_bookmark(&mut self, mut bookmark: Bookmark, now: Timestamp) -> ArenaId<Bookmark> {
+        let tags = std::mem::take(&mut bookmark.tags);
+        let (created_at, updated_at) = (bookmark.created_at, bookmark.updated_at);
+        let last_visited_at = bookmark.last_visited_at;
+        let collection = bookmark.collection;
+
+        let id = match self.find(bookmark.id) {
+            Some(id) => {
+                self.restore_bookmark(id);
+                self.update_bookmark(id, &bookmark.title, &bookmark.link, &bookmark.note, now);
+
+                let removed_tags: Vec<Rc<str>> = self
+                    .bookmark(id)
+                    .tags
+                    .iter()
+                    .filter(|x| !tags.contains(x))
+                    .cloned()
+                    .collect();
+                for tag in removed_tags {
+                    self.untag_bookmark(id, &tag, now);
+                }
+                id
+            }
+            None => {
+                bookmark.deleted_at = None;
+                self.add_bookmark(bookmark)
+            }
+        };
+        for tag in tags.iter() {
+            self.tag_bookmark(id, tag, now);
+        }
+
+        let bookmark = self.bookmarks.entry_mut(id).value;
+        bookmark.created_at = created_at;
+        bookmark.updated_at = updated_at;
+        bookmark.last_visited_at = bookmark.last_visited_at.max(last_visited_at);
+        bookmark.collection = collection;
+        self.changes.push(id);
+
+        id
+    }
+    pub fn visit_bookmark(&mut self, id: ArenaId<Bookmark>, now: Timestamp) {
+        self.bookmarks.entry_mut(id).value.last_visited_at = Some(now);
+        self.changes.push(id);
+    }
+    /// Moves the bookmark to trash, it is kept until [`Store::purge_trash`] removes it.
+    pub fn delete_bookmark(&mut self, id: ArenaId<Bookmark>, now: Timestamp) {
+        let bookmark = self.bookmarks.entry_mut(id).value;
+        if bookmark.deleted_at.is_some() {
+            return;
+        }
+        bookmark.deleted_at = Some(now);
+        self.trash.push(id);
+        self.changes.push(id);
+        self.unindex_bookmark(id);
+    }
+    pub fn restore_bookmark(&mut self, id: ArenaId<Bookmark>) {
+        let bookmark = self.bookmarks.entry_mut(id).value;
+        if bookmark.deleted_at.take().is_none() {
+            return;
+        }
+        self.trash.retain(|x| *x != id);
+        self.changes.push(id);
+        self.index_bookmark(id);
+    }
+    /// Removes the bookmark right away, it doesn't go to trash like [`Store::delete_bookmark`]
+    pub fn remove_bookmark(&mut self, id: ArenaId<Bookmark>) {
+        let bookmark = self.bookmarks.entry(id).value;
+        let bookmark_id = bookmark.id;
+        if bookmark.deleted_at.is_none() {
+            self.unindex_bookmark(id);
+        } else {
+            self.trash.retain(|x| *x != id);
+        }
+
+        self.ids.remove(&bookmark_id);
+        self.removals.push(bookmark_id);
+        self.bookmarks.remove(id);
+        self.changes.retain(|x| *x != id);
+    }
+    /// Removes bookmarks that stayed in trash longer than `retention`.
+    pub fn purge_trash(&mut self, now: Timestamp, retention: Timestamp) {
+        let bookmarks = &mut self.bookmarks;
+        let ids = &mut self.ids;
+        let removals = &mut self.removals;
+        let mut purged = Vec::new();
+
+        self.trash.retain(|id| {
+            let bookmark = bookmarks.entry(*id).value;
+            let deleted_at = bookmark.deleted_at.unwrap_or(now);
+            if now.saturating_sub(deleted_at) < retention {
+                return true;
+            }
+            ids.remove(&bookmark.id);
+            removals.push(bookmark.id);
+            bookmarks.remove(*id);
+            purged.push(*id);
+            false
+        });
+        self.changes.retain(|id| !purged.contains(id));
+    }
+    /// Arena slots of bookmarks matching a tag expression, see [`TagQuery`]. Trash never matches.
+    pub fn query_tags(&self, query: &str) -> Result<BitField, QueryError> {
+        let query = query::parse(query)?;
+
+        let mut live = BitField::init(self.bookmarks.len());
+        for id in self.all_ids() {
+            live.set(id.id() as usize, true);
+        }
+
+        Ok(self.evaluate_tags(&query, &live))
+    }
+    fn evaluate_tags(&self, query: &TagQuery, live: &BitField) -> BitField {
+        match query {
+            TagQuery::Tag(name) => self
+                .tag_id(name)
+                .and_then(|tag| self.tags.get(&tag))
+                .map(|container| container.tags.clone())
+                .unwrap_or_default(),
+            TagQuery::Not(inner) => {
+                let mut bits = self.evaluate_tags(inner, live);
+                bits.and(live);
+                bits.xor(live);
+                bits
+            }
+            TagQuery::And(left, right) => {
+                let mut bits = self.evaluate_tags(left, live);
+                bits.and(&self.evaluate_tags(right, live));
+                bits
+            }
+            TagQuery::Or(left, right) => {
+                let mut bits = self.evaluate_tags(left, live);
+                bits.or(&self.evaluate_tags(right, live));
+                bits
+            }
+        }
+    }
+    /// Bookmarks that have a term starting with each of the query's tokens. Empty query matches
+    /// every bookmark that is not in trash. If `mask` is given, only bookmarks with their arena
+    /// slot set are kept, see [`Store::query_tags`].
+    pub fn search(
+        &mut self,
+        query: &str,
+        mask: Option<&BitField>,
+        sort: SortOrder,
+    ) -> &[ArenaId<Bookmark>] {
+        let mut filtered_items = std::mem::take(&mut self.filtered_items);
+        filtered_items.clear();
+
+        let mut matches: Option<HashSet<ArenaId<Bookmark>>> = None;
+
+        for token in search::tokenize(query) {
+            let mut token_matches = HashSet::new();
+            for (_, ids) in self
+                .search_index
+                .range::<str, _>((Bound::Included(token.as_str()), Bound::Unbounded))
+                .take_while(|(term, _)| term.starts_with(&token))
+            {
+                token_matches.extend(ids.iter().copied());
+            }
+
+            matches = Some(match matches {
+                Some(matches) => matches.intersection(&token_matches).copied().collect(),
+                None => token_matches,
+            });
+        }
+
+        match matches {
+            Some(matches) => filtered_items.extend(matches),
+            None => filtered_items.extend(self.all_ids()),
+        }
+        if let Some(mask) = mask {
+            filtered_items.retain(|id| mask.get(id.id() as usize));
+        }
+        self.sort(&mut filtered_items, sort);
+
+        self.filtered_items = filtered_items;
+        &self.filtered_items
+    }
+    /// Ties are broken by arena id, so the order is stable between searches
+    fn sort(&self, ids: &mut [ArenaId<Bookmark>], sort: SortOrder) {
+        use std::cmp::Reverse;
+
+        ids.sort_unstable();
+
+        let bookmarks = &self.bookmarks;
+        let bookmark = |id: &ArenaId<Bookmark>| bookmarks.entry(*id).value;
+
+        match sort {
+            SortOrder::Newest => ids.sort_by_key(|id| Reverse(bookmark(id).created_at)),
+            SortOrder::Oldest => ids.sort_by_key(|id| bookmark(id).created_at),
+            SortOrder::RecentlyVisited => {
+                ids.sort_by_key(|id| Reverse(bookmark(id).last_visited_at))
+            }
+            SortOrder::Title => ids.sort_by_cached_key(|id| bookmark(id).title.to_lowercase()),
+            SortOrder::Domain => ids.sort_by_cached_key(|id| {
+                let bookmark = bookmark(id);
+                (
+                    search::domain(&bookmark.link),
+                    bookmark.title.to_lowercase(),
+                )
+            }),
+        }
+    }
+    /// Adds to search index, tags and links
+    fn index_bookmark(&mut self, id: ArenaId<Bookmark>) {
+        let link = link::normalize(&self.bookmarks.entry(id).value.link);
+        self.links.entry(link).or_default().insert(id);
+        for term in search::bookmark_terms(self.bookmarks.entry(id).value) {
+            self.search_index.entry(term).or_default().insert(id);
+        }
+        for name in self.bookmarks.entry(id).value.tags.clone() {
+            let tag = self.intern(&name);
+            self.create_tag(tag);
+            self.set_tag_bit(tag, id, true);
+        }
+    }
+    /// Removes from search index, tags and links
+    fn unindex_bookmark(&mut self, id: ArenaId<Bookmark>) {
+        let link = link::normalize(&self.bookmarks.entry(id).value.link);
+        if let Some(ids) = self.links.get_mut(&link) {
+            ids.remove(&id);
+            if ids.is_empty() {
+                self.links.remove(&link);
+            }
+        }
+        for name in self.bookmarks.entry(id).value.tags.clone() {
+            if let Some(tag) = self.tag_id(&name) {
+                self.set_tag_bit(tag, id, false);
+            }
+        }
+        for term in search::bookmark_terms(self.bookmarks.entry(id).value) {
+            if let Some(ids) = self.search_index.get_mut(&term) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.search_index.remove(&term);
+                }
+            }
+        }
+    }
     pub fn bookmark(&self, id: ArenaId<Bookmark>) -> &Bookmark {
         self.bookmarks.entry(id).value
     }
-    pub fn all_data<'a>(&'a self) -> impl Iterator<Item = &'a Bookmark> {
-        self.bookmarks.iter().map(|x| x.value)
+    /// None if the bookmark was removed, by an undo for instance
+    pub fn try_bookmark(&self, id: ArenaId<Bookmark>) -> Option<&Bookmark> {
+        self.bookmarks.try_entry(id).map(|x| x.value)
+    }
+    pub fn find(&self, id: BookmarkId) -> Option<ArenaId<Bookmark>> {
+        self.ids.get(&id).copied()
     }
-    pub fn all_ids<'a>(&'a self) -> IterArenaIds<'a, Bookmark> {
-        self.bookmarks.iter_ids()
+    /// Bookmarks that are not in trash.
+    pub fn all_data(&self) -> impl Iterator<Item = &Bookmark> {
+        self.bookmarks
+            .iter()
+            .map(|x| x.value)
+            .filter(|x| x.deleted_at.is_none())
+    }
+    /// Ids of bookmarks that are not in trash.
+    pub fn all_ids<'a>(&'a self) -> impl Iterator<Item = ArenaId<Bookmark>> + 'a {
+        self.bookmarks
+            .iter_ids()
+            .filter(|id| self.bookmarks.entry(*id).value.deleted_at.is_none())
+    }
+    /// Ids of bookmarks in trash, most recently deleted first.
+    pub fn trash_ids<'a>(&'a self) -> impl Iterator<Item = ArenaId<Bookmark>> + 'a {
+        self.trash.iter().rev().copied()
     }
     pub fn changes(&mut self) -> Option<impl Iterator<Item = ArenaId<Bookmark>> + '_> {
         if self.changes.is_empty() {
@@ -121,6 +757,26 @@ impl Store {
             Some(self.changes.drain(..))
         }
     }
+    pub fn removals(&mut self) -> Option<impl Iterator<Item = BookmarkId> + '_> {
+        if self.removals.is_empty() {
+            None
+        } else {
+            Some(self.removals.drain(..))
+        }
+    }
+    /// Puts back changes and removals that couldn't be persisted
+    pub fn requeue(&mut self, changes: Vec<ArenaId<Bookmark>>, removals: Vec<BookmarkId>) {
+        for id in changes {
+            if self.bookmarks.try_entry(id).is_some() && !self.changes.contains(&id) {
+                self.changes.push(id);
+            }
+        }
+        for id in removals {
+            if !self.removals.contains(&id) {
+                self.removals.push(id);
+            }
+        }
+    }
 }
 
 impl Default for Store {
@@ -130,9 +786,212 @@ impl Default for Store {
             interned: vec![Rc::from("DANGLING")],
             reverse_interned: HashMap::with_capacity(128),
             bookmarks: Arena::with_capacity(1024),
+            ids: HashMap::with_capacity(1024),
             filtered_items: Vec::with_capacity(1024),
             tags: HashMap::with_capacity(64),
+            search_index: BTreeMap::new(),
+            links: HashMap::with_capacity(1024),
+            trash: Vec::with_capacity(64),
             changes: Vec::with_capacity(128),
+            removals: Vec::with_capacity(64),
+            collections: Arena::with_capacity(64),
+            collection_ids: HashMap::with_capacity(64),
+            collection_changes: Vec::with_capacity(16),
+            collection_removals: Vec::with_capacity(16),
+            history: Vec::with_capacity(16),
+            transfers: Vec::new(),
         }
     }
 }
+
+#[cfg(test)]
+impl Bookmark {
+    /// For test fixtures, other fields can be overridden with struct update syntax
+    pub fn with_tags(mut self, tags: &[&str]) -> Self {
+        self.tags = tags.iter().map(|x| Rc::from(*x)).collect();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bookmark, Collection, Command, ImportReport, SortOrder, Store};
+    use crate::{settings::Settings, time::DAY};
+
+    #[test]
+    fn imports_new_bookmarks_with_their_timestamps() {
+        let mut store = Store::new();
+        let mut rust =
+            Bookmark::new("Rust", "https://www.rust-lang.org/", "", 1).with_tags(&["rust", "lang"]);
+        rust.updated_at = 2;
+        let languages = Collection::new("Languages", None, 1);
+        rust.collection = Some(languages.id);
+
+        let (commands, report) = store.import([rust.clone()], [languages.clone()]);
+        let reverted = Command::Batch(commands).apply(&mut store, 10);
+
+        assert_eq!(
+            report,
+            ImportReport {
+                added: 1,
+                ..ImportReport::default()
+            }
+        );
+        let id = store.find(rust.id).unwrap();
+        assert_eq!(store.bookmark(id), &rust);
+        assert_eq!(store.tags(), vec![("lang".into(), 1), ("rust".into(), 1)]);
+        let collection = store.find_collection(languages.id).unwrap();
+        assert_eq!(store.collection(collection), &languages);
+
+        reverted.apply(&mut store, 11);
+        assert_eq!(store.find(rust.id), None);
+        assert_eq!(store.find_collection(languages.id), None);
+        assert!(store.tags().is_empty());
+    }
+
+    #[test]
+    fn merges_imported_bookmarks_by_id_then_link() {
+        let mut store = Store::new();
+        let rust =
+            store.create_bookmark(Bookmark::new("Rust", "https://www.rust-lang.org/", "", 1));
+        store.create_bookmark(Bookmark::new("Docs", "https://doc.rust-lang.org/", "", 1));
+        let old = store.create_bookmark(Bookmark::new("Old", "https://old.com/", "", 1));
+        store.delete_bookmark(old, 2);
+
+        let tokio = Bookmark::new("Tokio", "https://tokio.rs/", "", 3);
+        let (commands, report) = store.import(
+            [
+                // Same id, changed
+                Bookmark {
+                    id: store.bookmark(rust).id,
+                    ..Bookmark::new("Rust lang", "https://www.rust-lang.org/", "", 3)
+                },
+                // Same link, nothing new
+                Bookmark::new("Docs", "https://doc.rust-lang.org/", "", 3),
+                // Same id, restored from trash
+                store.bookmark(old).clone(),
+                tokio.clone(),
+                // Same canonical link as the one just added, keeps its link
+                Bookmark::new("Tokio", "HTTPS://tokio.rs?utm_source=feed", "", 4),
+            ],
+            [],
+        );
+        Command::Batch(commands).apply(&mut store, 10);
+
+        assert_eq!(
+            report,
+            ImportReport {
+                added: 1,
+                updated: 2,
+                skipped: 2,
+            }
+        );
+        assert_eq!(&*store.bookmark(rust).title, "Rust lang");
+        assert_eq!(store.bookmark(rust).updated_at, 10);
+        assert_eq!(store.bookmark(old).deleted_at, None);
+        assert_eq!(
+            store.find_by_link("https://tokio.rs"),
+            vec![store.find(tokio.id).unwrap()]
+        );
+        assert_eq!(
+            &*store.bookmark(store.find(tokio.id).unwrap()).link,
+            "https://tokio.rs/"
+        );
+        assert_eq!(store.search("", None, SortOrder::Newest).len(), 4);
+    }
+
+    #[test]
+    fn keeps_deleted_bookmarks_in_trash_until_restored() {
+        let mut store = Store::new();
+        let rust = store.create_bookmark(
+            Bookmark::new("Rust", "https://www.rust-lang.org/", "", 1).with_tags(&["lang"]),
+        );
+        let tokio = store.create_bookmark(Bookmark::new("Tokio", "https://tokio.rs/", "", 2));
+
+        store.delete_bookmark(rust, 3);
+        assert_eq!(store.bookmark(rust).deleted_at, Some(3));
+        assert_eq!(store.search("", None, SortOrder::Newest), &[tokio]);
+        assert!(store.search("rust", None, SortOrder::Newest).is_empty());
+        assert_eq!(store.query_tags("lang").unwrap().count_ones(), 0);
+        assert_eq!(store.trash_ids().collect::<Vec<_>>(), vec![rust]);
+
+        // Deleting again keeps the first deletion time
+        store.delete_bookmark(rust, 4);
+        assert_eq!(store.bookmark(rust).deleted_at, Some(3));
+
+        store.restore_bookmark(rust);
+        assert_eq!(store.bookmark(rust).deleted_at, None);
+        assert_eq!(store.search("rust", None, SortOrder::Newest), &[rust]);
+        assert!(store.query_tags("lang").unwrap().get(rust.id() as usize));
+        assert_eq!(store.trash_ids().count(), 0);
+    }
+
+    #[test]
+    fn purges_trash_past_retention() {
+        let retention = Settings {
+            trash_retention_days: 7,
+            ..Settings::default()
+        }
+        .trash_retention();
+        let mut store = Store::new();
+        let old = store.create_bookmark(Bookmark::new("Old", "https://old.com/", "", 0));
+        let recent = store.create_bookmark(Bookmark::new("Recent", "https://recent.com/", "", 0));
+        let kept = store.create_bookmark(Bookmark::new("Kept", "https://kept.com/", "", 0));
+        let old_id = store.bookmark(old).id;
+        store.delete_bookmark(old, DAY);
+        store.delete_bookmark(recent, 5 * DAY);
+        store.changes();
+
+        store.purge_trash(8 * DAY, retention);
+
+        assert_eq!(store.find(old_id), None);
+        assert_eq!(store.trash_ids().collect::<Vec<_>>(), vec![recent]);
+        assert_eq!(
+            store.removals().map(|x| x.collect::<Vec<_>>()),
+            Some(vec![old_id])
+        );
+        assert_eq!(store.search("", None, SortOrder::Newest), &[kept]);
+    }
+
+    #[test]
+    fn searches_by_term_prefixes() {
+        let mut store = Store::new();
+        let rust = store.create_bookmark(Bookmark::new(
+            "The Rust book",
+            "https://doc.rust-lang.org/book/",
+            "",
+            1,
+        ));
+        let tokio = store.create_bookmark(Bookmark::new(
+            "Tokio",
+            "https://tokio.rs/",
+            "Async Rust runtime",
+            2,
+        ));
+
+        // Empty query and query without tokens match everything, newest first
+        assert_eq!(store.search("", None, SortOrder::Newest), &[tokio, rust]);
+        assert_eq!(store.search(" - ", None, SortOrder::Newest), &[tokio, rust]);
+
+        assert_eq!(store.search("RUS", None, SortOrder::Newest), &[tokio, rust]);
+        assert_eq!(store.search("rust bo", None, SortOrder::Newest), &[rust]);
+        assert_eq!(store.search("tokio.rs", None, SortOrder::Newest), &[tokio]);
+        assert_eq!(store.search("rust-lang", None, SortOrder::Newest), &[rust]);
+        assert_eq!(store.search("lang", None, SortOrder::Newest), &[rust]);
+        assert!(store.search("rusty", None, SortOrder::Newest).is_empty());
+        assert!(store
+            .search("book async", None, SortOrder::Newest)
+            .is_empty());
+
+        store.update_bookmark(tokio, "Tokio", "https://tokio.rs/", "Event loop", 3);
+        assert!(store.search("async", None, SortOrder::Newest).is_empty());
+        assert_eq!(store.search("event", None, SortOrder::Newest), &[tokio]);
+
+        store.delete_bookmark(rust, 4);
+        assert!(store.search("book", None, SortOrder::Newest).is_empty());
+        assert_eq!(store.search("", None, SortOrder::Newest), &[tokio]);
+
+        store.restore_bookmark(rust);
+        assert_eq!(store.search("book", None, SortOrder::Newest), &[rust]);
+    }
+}
@@ -0,0 +1,413 @@
+//! Collections are folders bookmarks are filed in, like the ones browsers have. A collection can
+//! be inside another one and a bookmark is in at most one collection.
+//!
+//! Both sides refer to collections by [`CollectionId`], so a bookmark or collection whose
+//! collection is gone is treated as being at the top level.
+
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Bookmark, Store};
+use crate::{arena::ArenaId, bit_field::BitField, time::Timestamp};
+
+/// Identifies a collection across reloads and files, like [`super::BookmarkId`]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CollectionId(Uuid);
+
+impl CollectionId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for CollectionId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for CollectionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: CollectionId,
+    pub name: Rc<str>,
+    /// None at the top level
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<CollectionId>,
+    #[serde(default)]
+    pub created_at: Timestamp,
+    /// Last change of name or parent
+    #[serde(default)]
+    pub updated_at: Timestamp,
+}
+
+impl Collection {
+    /// Not in a store yet, see [`Store::create_collection`]. Empty names are replaced with
+    /// "Untitled".
+    pub fn new(name: &str, parent: Option<CollectionId>, now: Timestamp) -> Self {
+        let name = match name.trim() {
+            "" => "Untitled",
+            name => name,
+        };
+
+        Self {
+            id: CollectionId::new(),
+            name: Rc::from(name),
+            parent,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// A row of the collection tree, see [`Store::collection_tree`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct CollectionNode {
+    pub id: ArenaId<Collection>,
+    pub name: Rc<str>,
+    /// 0 at the top level
+    pub depth: usize,
+    pub has_children: bool,
+    /// Bookmarks right in it, not in the ones inside it. Trash is not counted.
+    pub count: usize,
+}
+
+impl Store {
+    /// Collection's [`CollectionId`] should not be in the store already, see
+    /// [`Store::find_collection`]
+    pub fn add_collection(&mut self, collection: Collection) -> ArenaId<Collection> {
+        let collection_id = collection.id;
+        let id = self.collections.add(collection);
+        self.collection_ids.insert(collection_id, id);
+        id
+    }
+    /// Like [`Store::add_collection`], but it is a change to persist, see
+    /// [`Store::create_bookmark`]
+    pub fn create_collection(&mut self, collection: Collection) -> ArenaId<Collection> {
+        let collection_id = collection.id;
+        let id = self.add_collection(collection);
+        self.collection_removals.retain(|x| *x != collection_id);
+        self.collection_changes.push(id);
+
+        id
+    }
+    /// Does nothing if `parent` is the collection itself or inside it
+    pub fn move_collection(
+        &mut self,
+        id: ArenaId<Collection>,
+        parent: Option<ArenaId<Collection>>,
+        now: Timestamp,
+    ) {
+        if let Some(parent) = parent {
+            let is_inside = self.ancestors(parent).any(|x| x == id);
+            if is_inside {
+                return;
+            }
+        }
+
+        let parent = parent.map(|x| self.collection(x).id);
+        let collection = self.collections.entry_mut(id).value;
+        if collection.parent == parent {
+            return;
+        }
+        collection.parent = parent;
+        collection.updated_at = now;
+        self.collection_changes.push(id);
+    }
+    /// Collections and bookmarks in it are moved to its parent
+    pub fn delete_collection(&mut self, id: ArenaId<Collection>, now: Timestamp) {
+        let collection = self.collections.entry(id).value;
+        let (collection_id, parent) = (collection.id, collection.parent);
+
+        for child in self.collections.iter_ids().collect::<Vec<_>>() {
+            let child_collection = self.collections.entry_mut(child).value;
+            if child_collection.parent == Some(collection_id) {
+                child_collection.parent = parent;
+                child_collection.updated_at = now;
+                self.collection_changes.push(child);
+            }
+        }
+        for bookmark in self.bookmarks.iter_ids().collect::<Vec<_>>() {
+            let entry = self.bookmarks.entry_mut(bookmark).value;
+            if entry.collection == Some(collection_id) {
+                entry.collection = parent;
+                entry.updated_at = now;
+                self.changes.push(bookmark);
+            }
+        }
+
+        self.drop_collection(id);
+    }
+    /// Makes the collection with the same [`CollectionId`] equal to `collection`, or adds it.
+    /// Applies changes made elsewhere, so the parent is not checked.
+    pub fn put_collection(&mut self, collection: Collection) -> ArenaId<Collection> {
+        let id = match self.find_collection(collection.id) {
+            Some(id) => {
+                *self.collections.entry_mut(id).value = collection;
+                id
+            }
+            None => self.add_collection(collection),
+        };
+        self.collection_changes.push(id);
+
+        id
+    }
+    /// Removes only the collection, what is in it keeps pointing at it and shows at the top level.
+    /// [`Store::delete_collection`] moves it out first.
+    pub fn drop_collection(&mut self, id: ArenaId<Collection>) {
+        let collection_id = self.collection(id).id;
+
+        self.collection_ids.remove(&collection_id);
+        self.collection_removals.push(collection_id);
+        self.collections.remove(id);
+        self.collection_changes.retain(|x| *x != id);
+    }
+    /// Removes the collection like [`Store::delete_collection`], if it is in the store
+    pub fn remove_collection(&mut self, id: CollectionId, now: Timestamp) {
+        if let Some(id) = self.find_collection(id) {
+            self.delete_collection(id, now);
+        }
+    }
+    /// Moves the bookmark out of its collection, into `collection` or to the top level
+    pub fn file_bookmark(
+        &mut self,
+        id: ArenaId<Bookmark>,
+        collection: Option<ArenaId<Collection>>,
+        now: Timestamp,
+    ) {
+        let collection = collection.map(|x| self.collection(x).id);
+        let bookmark = self.bookmarks.entry_mut(id).value;
+        if bookmark.collection == collection {
+            return;
+        }
+        bookmark.collection = collection;
+        bookmark.updated_at = now;
+        self.changes.push(id);
+    }
+    pub fn collection(&self, id: ArenaId<Collection>) -> &Collection {
+        self.collections.entry(id).value
+    }
+    /// None if the collection was deleted
+    pub fn try_collection(&self, id: ArenaId<Collection>) -> Option<&Collection> {
+        self.collections.try_entry(id).map(|x| x.value)
+    }
+    pub fn find_collection(&self, id: CollectionId) -> Option<ArenaId<Collection>> {
+        self.collection_ids.get(&id).copied()
+    }
+    /// Changes made elsewhere can leave parents in a cycle, so at most as many as there are
+    /// collections
+    fn ancestors(&self, id: ArenaId<Collection>) -> impl Iterator<Item = ArenaId<Collection>> + '_ {
+        std::iter::successors(Some(id), |x| self.parent_collection(*x))
+            .take(self.collections.count())
+    }
+    fn parent_collection(&self, id: ArenaId<Collection>) -> Option<ArenaId<Collection>> {
+        self.collection(id)
+            .parent
+            .and_then(|x| self.find_collection(x))
+    }
+    pub fn all_collections(&self) -> impl Iterator<Item = &Collection> {
+        self.collections.iter().map(|x| x.value)
+    }
+    /// Every collection depth first, children sorted by name after their parent. Collections in a
+    /// parent cycle are never reached from the top level, so the cycle is cut at one of them and
+    /// it is shown there.
+    pub fn collection_tree(&self) -> Vec<CollectionNode> {
+        let by_name = |x: &ArenaId<Collection>| (self.collection(*x).name.to_lowercase(), *x);
+        let mut children: HashMap<Option<ArenaId<Collection>>, Vec<ArenaId<Collection>>> =
+            HashMap::new();
+        for id in self.collections.iter_ids() {
+            children
+                .entry(self.parent_collection(id))
+                .or_default()
+                .push(id);
+        }
+        for ids in children.values_mut() {
+            ids.sort_by_cached_key(by_name);
+        }
+
+        let mut counts: HashMap<ArenaId<Collection>, usize> = HashMap::new();
+        for bookmark in self.all_data() {
+            if let Some(id) = bookmark.collection.and_then(|x| self.find_collection(x)) {
+                *counts.entry(id).or_default() += 1;
+            }
+        }
+
+        let top_level = children.get(&None).cloned().unwrap_or_default();
+        let mut all: Vec<ArenaId<Collection>> = self.collections.iter_ids().collect();
+        all.sort_by_cached_key(by_name);
+        // The last ancestor is in the cycle, or at the top level if there is none
+        let cycles = all.into_iter().filter_map(|x| self.ancestors(x).last());
+
+        let mut nodes = Vec::with_capacity(self.collections.count());
+        let mut reached: HashSet<ArenaId<Collection>> = HashSet::new();
+        for root in top_level.into_iter().chain(cycles) {
+            if reached.contains(&root) {
+                continue;
+            }
+            let mut stack = vec![(root, 0)];
+            while let Some((id, depth)) = stack.pop() {
+                reached.insert(id);
+                let ids: Vec<ArenaId<Collection>> = children
+                    .get(&Some(id))
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .filter(|x| !reached.contains(x))
+                    .collect();
+                nodes.push(CollectionNode {
+                    id,
+                    name: self.collection(id).name.clone(),
+                    depth,
+                    has_children: !ids.is_empty(),
+                    count: counts.get(&id).copied().unwrap_or(0),
+                });
+                // Reversed, so the first child is popped first
+                stack.extend(ids.into_iter().rev().map(|x| (x, depth + 1)));
+            }
+        }
+
+        nodes
+    }
+    /// Arena slots of bookmarks in the collection or in a collection inside it, like
+    /// [`Store::query_tags`]. Trash never matches.
+    pub fn query_collection(&self, id: ArenaId<Collection>) -> BitField {
+        let mut bits = BitField::init(self.bookmarks.len());
+        for bookmark in self.all_ids() {
+            let collection = self
+                .bookmark(bookmark)
+                .collection
+                .and_then(|x| self.find_collection(x));
+            let is_inside = collection
+                .into_iter()
+                .flat_map(|x| self.ancestors(x))
+                .any(|x| x == id);
+            if is_inside {
+                bits.set(bookmark.id() as usize, true);
+            }
+        }
+        bits
+    }
+    pub fn collection_changes(&mut self) -> Option<impl Iterator<Item = ArenaId<Collection>> + '_> {
+        if self.collection_changes.is_empty() {
+            None
+        } else {
+            Some(self.collection_changes.drain(..))
+        }
+    }
+    pub fn collection_removals(&mut self) -> Option<impl Iterator<Item = CollectionId> + '_> {
+        if self.collection_removals.is_empty() {
+            None
+        } else {
+            Some(self.collection_removals.drain(..))
+        }
+    }
+    /// Puts back collection changes and removals that couldn't be persisted, like
+    /// [`Store::requeue`]
+    pub fn requeue_collections(
+        &mut self,
+        changes: Vec<ArenaId<Collection>>,
+        removals: Vec<CollectionId>,
+    ) {
+        for id in changes {
+            if self.collections.try_entry(id).is_some() && !self.collection_changes.contains(&id) {
+                self.collection_changes.push(id);
+            }
+        }
+        for id in removals {
+            if !self.collection_removals.contains(&id) {
+                self.collection_removals.push(id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::{Bookmark, Collection, Store};
+
+    #[test]
+    fn builds_tree_and_moves() {
+        let mut store = Store::new();
+        let work = store.create_collection(Collection::new("Work", None, 1));
+        let rust =
+            store.create_collection(Collection::new("rust", Some(store.collection(work).id), 1));
+        let archive = store.create_collection(Collection::new("Archive", None, 1));
+
+        let bookmark =
+            store.create_bookmark(Bookmark::new("Rust", "https://www.rust-lang.org/", "", 1));
+        store.file_bookmark(bookmark, Some(rust), 2);
+
+        let tree = |store: &Store| {
+            store
+                .collection_tree()
+                .into_iter()
+                .map(|x| (x.name.to_string(), x.depth, x.count))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            tree(&store),
+            vec![
+                ("Archive".to_string(), 0, 0),
+                ("Work".to_string(), 0, 0),
+                ("rust".to_string(), 1, 1),
+            ]
+        );
+        assert!(store.query_collection(work).get(bookmark.id() as usize));
+        assert!(!store.query_collection(archive).get(bookmark.id() as usize));
+
+        // Can't be moved inside itself
+        store.move_collection(work, Some(rust), 3);
+        assert_eq!(store.collection(work).parent, None);
+
+        store.move_collection(work, Some(archive), 3);
+        store.delete_collection(work, 4);
+        assert_eq!(
+            tree(&store),
+            vec![("Archive".to_string(), 0, 0), ("rust".to_string(), 1, 1)]
+        );
+        assert_eq!(
+            store.collection(rust).parent,
+            Some(store.collection(archive).id)
+        );
+    }
+
+    #[test]
+    fn shows_collections_in_a_parent_cycle_at_the_top_level() {
+        let mut store = Store::new();
+        let a = Collection::new("A", None, 1);
+        let b = Collection::new("B", Some(a.id), 1);
+        let c = Collection::new("C", Some(b.id), 1);
+        // Each side of a merge moved one inside the other
+        store.put_collection(Collection {
+            parent: Some(b.id),
+            ..a
+        });
+        store.put_collection(b);
+        store.put_collection(c);
+
+        let tree: Vec<_> = store
+            .collection_tree()
+            .into_iter()
+            .map(|x| (x.name.to_string(), x.depth, x.has_children))
+            .collect();
+        assert_eq!(
+            tree,
+            vec![
+                ("A".to_string(), 0, true),
+                ("B".to_string(), 1, true),
+                ("C".to_string(), 2, false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Bookmarks are indexed by [`link::normalize`]d link, so saving a page twice can be warned
+//! about and duplicates can be merged. Trash is not indexed.
+
+use super::{Bookmark, Command, Store};
+use crate::{arena::ArenaId, link};
+
+impl Store {
+    /// Bookmarks not in trash whose link has the same canonical form as `link`, oldest first
+    pub fn find_by_link(&self, link: &str) -> Vec<ArenaId<Bookmark>> {
+        let mut ids: Vec<_> = self
+            .links
+            .get(&link::normalize(link))
+            .map(|x| x.iter().copied().collect())
+            .unwrap_or_default();
+        self.sort_oldest(&mut ids);
+        ids
+    }
+    /// Every link saved more than once with its bookmarks, oldest first, by oldest bookmark
+    pub fn duplicates(&self) -> Vec<Vec<ArenaId<Bookmark>>> {
+        let mut groups: Vec<Vec<ArenaId<Bookmark>>> = self
+            .links
+            .values()
+            .filter(|x| x.len() > 1)
+            .map(|x| {
+                let mut ids: Vec<_> = x.iter().copied().collect();
+                self.sort_oldest(&mut ids);
+                ids
+            })
+            .collect();
+        groups.sort_by_key(|x| (self.bookmark(x[0]).created_at, x[0]));
+        groups
+    }
+    fn sort_oldest(&self, ids: &mut [ArenaId<Bookmark>]) {
+        ids.sort_by_key(|x| (self.bookmark(*x).created_at, *x));
+    }
+    /// Keeps the oldest of the bookmarks, its note gets the other notes and it gets their tags
+    /// and collection if it has none. The others are moved to trash. Applied as commands, so a
+    /// merge can be undone.
+    pub fn merge_duplicates(&self, ids: &[ArenaId<Bookmark>]) -> Vec<Command> {
+        let mut ids: Vec<_> = ids
+            .iter()
+            .copied()
+            .filter(|x| {
+                self.try_bookmark(*x)
+                    .is_some_and(|x| x.deleted_at.is_none())
+            })
+            .collect();
+        self.sort_oldest(&mut ids);
+        let Some((kept, others)) = ids.split_first() else {
+            return Vec::new();
+        };
+        let kept = self.bookmark(*kept);
+        let others: Vec<&Bookmark> = others.iter().map(|x| self.bookmark(*x)).collect();
+
+        let mut notes: Vec<&str> = Vec::new();
+        for note in std::iter::once(kept)
+            .chain(others.iter().copied())
+            .map(|x| x.note.trim())
+        {
+            if !note.is_empty() && !notes.contains(&note) {
+                notes.push(note);
+            }
+        }
+
+        let mut commands = vec![Command::UpdateBookmark {
+            id: kept.id,
+            title: kept.title.clone(),
+            link: kept.link.clone(),
+            note: notes.join("\n\n").into(),
+        }];
+        for other in others.iter() {
+            commands.extend(other.tags.iter().map(|tag| Command::TagBookmark {
+                id: kept.id,
+                tag: tag.clone(),
+            }));
+        }
+        if kept.collection.is_none() {
+            if let Some(collection) = others.iter().find_map(|x| x.collection) {
+                commands.push(Command::FileBookmark {
+                    id: kept.id,
+                    collection: Some(collection),
+                });
+            }
+        }
+        commands.extend(others.iter().map(|x| Command::DeleteBookmark(x.id)));
+
+        commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::{Bookmark, Store, UndoStack};
+
+    #[test]
+    fn finds_and_merges_duplicates() {
+        let mut store = Store::new();
+        let rust = store.create_bookmark(Bookmark::new(
+            "Rust",
+            "https://www.rust-lang.org/",
+            "Language",
+            1,
+        ));
+        let copy = store.create_bookmark(Bookmark::new(
+            "Rust again",
+            "https://WWW.rust-lang.org?utm_source=feed",
+            "Fast",
+            2,
+        ));
+        let other =
+            store.create_bookmark(Bookmark::new("Docs", "https://doc.rust-lang.org/", "", 3));
+        store.tag_bookmark(copy, "lang", 2);
+
+        assert_eq!(
+            store.find_by_link("https://www.rust-lang.org#learn"),
+            vec![rust, copy]
+        );
+        assert_eq!(store.find_by_link("https://doc.rust-lang.org"), vec![other]);
+        assert_eq!(store.duplicates(), vec![vec![rust, copy]]);
+
+        let mut undo_stack = UndoStack::default();
+        let commands = store.merge_duplicates(&[copy, rust]);
+        undo_stack.apply(&mut store, commands, 4);
+
+        assert!(store.duplicates().is_empty());
+        let kept = store.bookmark(rust);
+        assert_eq!(&*kept.note, "Language\n\nFast");
+        assert_eq!(kept.tags, vec!["lang".into()]);
+        assert!(store.bookmark(copy).deleted_at.is_some());
+
+        // Trash is not indexed, restoring it makes it a duplicate again
+        undo_stack.undo(&mut store, 5);
+        assert_eq!(store.duplicates(), vec![vec![rust, copy]]);
+        assert_eq!(&*store.bookmark(rust).note, "Language");
+    }
+}
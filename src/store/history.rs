@@ -0,0 +1,82 @@
+//! Every change of a bookmark's title, link or note is kept as a [`HistoryEntry`] with what it
+//! was before. Entries are only written to storage, the store queues them like changes until
+//! they are persisted.
+
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Bookmark, BookmarkId, Store};
+use crate::{arena::ArenaId, time::Timestamp};
+
+/// A bookmark as it was until `changed_at`
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Indexed in IndexedDB to load one bookmark's history
+    pub bookmark: BookmarkId,
+    pub changed_at: Timestamp,
+    pub title: Rc<str>,
+    pub link: Rc<str>,
+    pub note: Rc<str>,
+}
+
+impl HistoryEntry {
+    pub fn is_same(&self, bookmark: &Bookmark) -> bool {
+        self.title == bookmark.title && self.link == bookmark.link && self.note == bookmark.note
+    }
+}
+
+impl Store {
+    /// Keeps the bookmark's title, link and note before they change at `now`
+    pub(super) fn record_history(&mut self, id: ArenaId<Bookmark>, now: Timestamp) {
+        let bookmark = self.bookmark(id);
+        self.history.push(HistoryEntry {
+            bookmark: bookmark.id,
+            changed_at: now,
+            title: bookmark.title.clone(),
+            link: bookmark.link.clone(),
+            note: bookmark.note.clone(),
+        });
+    }
+    pub fn history_changes(&mut self) -> Option<impl Iterator<Item = HistoryEntry> + '_> {
+        if self.history.is_empty() {
+            None
+        } else {
+            Some(self.history.drain(..))
+        }
+    }
+    /// Puts back entries that couldn't be persisted, like [`Store::requeue`]
+    pub fn requeue_history(&mut self, entries: Vec<HistoryEntry>) {
+        self.history.splice(0..0, entries);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::{Bookmark, Store};
+
+    #[test]
+    fn records_previous_values() {
+        let mut store = Store::new();
+        let id = store.create_bookmark(Bookmark::new("Rust", "https://rust-lang.org/", "", 1));
+        assert!(store.history_changes().is_none());
+
+        store.update_bookmark(id, "Rust", "https://rust-lang.org/", "", 2);
+        assert!(store.history_changes().is_none());
+
+        store.update_bookmark(id, "Rust", "https://www.rust-lang.org/", "Language", 3);
+        store.update_bookmark(id, "The Rust language", "https://www.rust-lang.org/", "", 4);
+        let entries: Vec<_> = store.history_changes().unwrap().collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].changed_at, 3);
+        assert_eq!(&*entries[0].link, "https://rust-lang.org/");
+        assert_eq!(&*entries[1].note, "Language");
+        assert!(entries.iter().all(|x| x.bookmark == store.bookmark(id).id));
+
+        store.requeue_history(entries.clone());
+        assert_eq!(
+            store.history_changes().unwrap().collect::<Vec<_>>(),
+            entries
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! Tag expressions like `rust AND (async OR wasm) NOT archived`.
+//!
+//! `NOT` binds tighter than `AND`, which binds tighter than `OR`. Tags written next to each other
+//! without an operator are joined with `AND`.
+
+use super::normalize_tag;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TagQuery {
+    Tag(String),
+    Not(Box<TagQuery>),
+    And(Box<TagQuery>, Box<TagQuery>),
+    Or(Box<TagQuery>, Box<TagQuery>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryError {
+    Empty,
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    UnclosedParenthesis,
+}
+
+impl std::error::Error for QueryError {}
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Empty query"),
+            Self::UnexpectedEnd => write!(f, "Query ended unexpectedly"),
+            Self::UnexpectedToken(token) => write!(f, "Unexpected \"{}\"", token),
+            Self::UnclosedParenthesis => write!(f, "Unclosed parenthesis"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Tag(String),
+}
+
+impl Token {
+    fn starts_operand(&self) -> bool {
+        matches!(self, Token::Not | Token::Open | Token::Tag(_))
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::And => write!(f, "AND"),
+            Self::Or => write!(f, "OR"),
+            Self::Not => write!(f, "NOT"),
+            Self::Open => write!(f, "("),
+            Self::Close => write!(f, ")"),
+            Self::Tag(tag) => write!(f, "{}", tag),
+        }
+    }
+}
+
+fn push_word(word: &mut String, tokens: &mut Vec<Token>) {
+    if word.is_empty() {
+        return;
+    }
+    let token = match word.as_str() {
+        "AND" => Token::And,
+        "OR" => Token::Or,
+        "NOT" => Token::Not,
+        _ => Token::Tag(normalize_tag(word)),
+    };
+    tokens.push(token);
+    word.clear();
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+
+    for c in input.chars() {
+        match c {
+            '(' | ')' => {
+                push_word(&mut word, &mut tokens);
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            c if c.is_whitespace() => push_word(&mut word, &mut tokens),
+            c => word.push(c),
+        }
+    }
+    push_word(&mut word, &mut tokens);
+
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+    fn or(&mut self) -> Result<TagQuery, QueryError> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.index += 1;
+            let right = self.and()?;
+            left = TagQuery::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+    fn and(&mut self) -> Result<TagQuery, QueryError> {
+        let mut left = self.not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.index += 1,
+                Some(token) if token.starts_operand() => {}
+                _ => break,
+            }
+            let right = self.not()?;
+            left = TagQuery::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+    fn not(&mut self) -> Result<TagQuery, QueryError> {
+        if self.peek() == Some(&Token::Not) {
+            self.index += 1;
+            return Ok(TagQuery::Not(Box::new(self.not()?)));
+        }
+        self.operand()
+    }
+    fn operand(&mut self) -> Result<TagQuery, QueryError> {
+        match self.next() {
+            Some(Token::Tag(tag)) => Ok(TagQuery::Tag(tag)),
+            Some(Token::Open) => {
+                let query = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(query),
+                    Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+                    None => Err(QueryError::UnclosedParenthesis),
+                }
+            }
+            Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+}
+
+pub fn parse(input: &str) -> Result<TagQuery, QueryError> {
+    let tokens = tokenize(input);
+    if tokens.is_empty() {
+        return Err(QueryError::Empty);
+    }
+
+    let mut parser = Parser { tokens, index: 0 };
+    let query = parser.or()?;
+
+    match parser.next() {
+        Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+        None => Ok(query),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, QueryError, TagQuery};
+    use crate::store::{Bookmark, Store};
+
+    fn tag(name: &str) -> Box<TagQuery> {
+        Box::new(TagQuery::Tag(name.to_string()))
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse("a OR b AND c"),
+            Ok(TagQuery::Or(
+                tag("a"),
+                Box::new(TagQuery::And(tag("b"), tag("c")))
+            ))
+        );
+        assert_eq!(
+            parse("a AND b OR c"),
+            Ok(TagQuery::Or(
+                Box::new(TagQuery::And(tag("a"), tag("b"))),
+                tag("c")
+            ))
+        );
+    }
+
+    #[test]
+    fn joins_adjacent_tags_with_and() {
+        assert_eq!(parse("a b"), parse("a AND b"));
+        assert_eq!(
+            parse("Web Dev (x)"),
+            Ok(TagQuery::And(
+                Box::new(TagQuery::And(tag("web"), tag("dev"))),
+                tag("x")
+            ))
+        );
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert_eq!(
+            parse("a NOT b"),
+            Ok(TagQuery::And(tag("a"), Box::new(TagQuery::Not(tag("b")))))
+        );
+        assert_eq!(
+            parse("NOT NOT a OR b"),
+            Ok(TagQuery::Or(
+                Box::new(TagQuery::Not(Box::new(TagQuery::Not(tag("a"))))),
+                tag("b")
+            ))
+        );
+    }
+
+    #[test]
+    fn parentheses_group() {
+        assert_eq!(
+            parse("(a OR b) AND c"),
+            Ok(TagQuery::And(
+                Box::new(TagQuery::Or(tag("a"), tag("b"))),
+                tag("c")
+            ))
+        );
+        assert_eq!(
+            parse("NOT (a OR b)"),
+            Ok(TagQuery::Not(Box::new(TagQuery::Or(tag("a"), tag("b")))))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_queries() {
+        assert_eq!(parse(""), Err(QueryError::Empty));
+        assert_eq!(parse("  "), Err(QueryError::Empty));
+        assert_eq!(parse("(a OR b"), Err(QueryError::UnclosedParenthesis));
+        assert_eq!(parse("(a OR"), Err(QueryError::UnexpectedEnd));
+        assert_eq!(
+            parse("a OR b)"),
+            Err(QueryError::UnexpectedToken(")".to_string()))
+        );
+        assert_eq!(parse("a AND"), Err(QueryError::UnexpectedEnd));
+        assert_eq!(parse("a NOT"), Err(QueryError::UnexpectedEnd));
+        assert_eq!(
+            parse("OR a"),
+            Err(QueryError::UnexpectedToken("OR".to_string()))
+        );
+        assert_eq!(
+            parse("a AND OR b"),
+            Err(QueryError::UnexpectedToken("OR".to_string()))
+        );
+        assert_eq!(
+            parse("()"),
+            Err(QueryError::UnexpectedToken(")".to_string()))
+        );
+    }
+
+    #[test]
+    fn unknown_tags_match_nothing() {
+        let mut store = Store::new();
+        let rust = store.create_bookmark(Bookmark::new("Rust", "https://rust-lang.org", "", 0));
+        store.tag_bookmark(rust, "rust", 0);
+
+        let matches = |store: &Store, query: &str| {
+            let bits = store.query_tags(query).unwrap();
+            store
+                .all_ids()
+                .filter(|x| bits.get(x.id() as usize))
+                .count()
+        };
+        assert_eq!(matches(&store, "missing"), 0);
+        assert_eq!(matches(&store, "rust AND missing"), 0);
+        assert_eq!(matches(&store, "rust OR missing"), 1);
+        assert_eq!(matches(&store, "NOT missing"), 1);
+    }
+}
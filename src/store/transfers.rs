@@ -0,0 +1,41 @@
+//! Bookmarks moved to another library. The other library isn't open, so the store queues a
+//! [`Transfer`] for its storage like a change, and the bookmark's history goes along with it.
+
+use super::{Bookmark, BookmarkId, Store};
+use crate::{arena::ArenaId, library::LibraryId};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Transfer {
+    /// Put the bookmark and its history in the library, before they are removed from this one
+    Out(Bookmark, LibraryId),
+    /// Take the bookmark's history back from the library and remove it there
+    In(BookmarkId, LibraryId),
+}
+
+impl Store {
+    /// Removes the bookmark like [`Store::remove_bookmark`] and queues putting it in `library`
+    pub fn move_bookmark(&mut self, id: ArenaId<Bookmark>, library: LibraryId) {
+        let bookmark = self.bookmark(id).clone();
+        self.remove_bookmark(id);
+        self.transfers.push(Transfer::Out(bookmark, library));
+    }
+    /// Creates the bookmark moved to `library` again and queues removing it there
+    pub fn return_bookmark(&mut self, bookmark: Bookmark, library: LibraryId) -> ArenaId<Bookmark> {
+        let bookmark_id = bookmark.id;
+        let id = self.create_bookmark(bookmark);
+        self.transfers.push(Transfer::In(bookmark_id, library));
+
+        id
+    }
+    pub fn transfers(&mut self) -> Option<impl Iterator<Item = Transfer> + '_> {
+        if self.transfers.is_empty() {
+            None
+        } else {
+            Some(self.transfers.drain(..))
+        }
+    }
+    /// Puts back transfers that couldn't be written, like [`Store::requeue`]
+    pub fn requeue_transfers(&mut self, transfers: Vec<Transfer>) {
+        self.transfers.splice(0..0, transfers);
+    }
+}
@@ -0,0 +1,481 @@
+//! Edits are applied to the store as [`Command`]s. Applying one returns the command that reverts
+//! it, [`UndoStack`] keeps those to undo and redo.
+//!
+//! Commands refer to bookmarks and collections by their persistent ids, so they still apply
+//! after one was removed and created again. A command whose bookmark or collection is gone, or
+//! that would change nothing, does nothing and reverts to nothing.
+
+use std::rc::Rc;
+
+use super::{normalize_tag, Bookmark, BookmarkId, Collection, CollectionId, Store};
+use crate::{library::LibraryId, time::Timestamp};
+
+/// Undo steps kept, older ones are dropped
+pub const UNDO_LIMIT: usize = 100;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    /// Puts back a removed bookmark with the same id, or adds a new one from [`Bookmark::new`]
+    CreateBookmark(Bookmark),
+    /// Removes without trash, only to undo [`Command::CreateBookmark`]
+    RemoveBookmark(BookmarkId),
+    /// Removes without trash and puts it in the other library, see [`Store::move_bookmark`]
+    MoveBookmark {
+        id: BookmarkId,
+        library: LibraryId,
+    },
+    /// Puts back a bookmark moved to the other library and removes it there, only to undo
+    /// [`Command::MoveBookmark`]
+    ReturnBookmark {
+        bookmark: Bookmark,
+        library: LibraryId,
+    },
+    UpdateBookmark {
+        id: BookmarkId,
+        title: Rc<str>,
+        link: Rc<str>,
+        note: Rc<str>,
+    },
+    /// Moves to trash
+    DeleteBookmark(BookmarkId),
+    RestoreBookmark(BookmarkId),
+    TagBookmark {
+        id: BookmarkId,
+        tag: Rc<str>,
+    },
+    UntagBookmark {
+        id: BookmarkId,
+        tag: Rc<str>,
+    },
+    /// Into the collection, or to the top level if None
+    FileBookmark {
+        id: BookmarkId,
+        collection: Option<CollectionId>,
+    },
+    CreateCollection(Collection),
+    /// Removes only the collection, only to undo [`Command::CreateCollection`]
+    RemoveCollection(CollectionId),
+    MoveCollection {
+        id: CollectionId,
+        parent: Option<CollectionId>,
+    },
+    /// Moves what is in it to its parent, see [`Store::delete_collection`]
+    DeleteCollection(CollectionId),
+    /// Applied in order, reverted in reverse order
+    Batch(Vec<Command>),
+}
+
+impl Command {
+    const NONE: Command = Command::Batch(Vec::new());
+
+    pub fn is_none(&self) -> bool {
+        match self {
+            Self::Batch(commands) => commands.iter().all(Command::is_none),
+            _ => false,
+        }
+    }
+
+    /// Returns the command that reverts it
+    pub fn apply(self, store: &mut Store, now: Timestamp) -> Command {
+        match self {
+            Self::CreateBookmark(bookmark) => {
+                if store.find(bookmark.id).is_some() {
+                    return Self::NONE;
+                }
+                let id = bookmark.id;
+                store.create_bookmark(bookmark);
+                Self::RemoveBookmark(id)
+            }
+            Self::RemoveBookmark(id) => {
+                let Some(arena_id) = store.find(id) else {
+                    return Self::NONE;
+                };
+                let bookmark = store.bookmark(arena_id).clone();
+                store.remove_bookmark(arena_id);
+                Self::CreateBookmark(bookmark)
+            }
+            Self::MoveBookmark { id, library } => {
+                let Some(arena_id) = store.find(id) else {
+                    return Self::NONE;
+                };
+                let bookmark = store.bookmark(arena_id).clone();
+                store.move_bookmark(arena_id, library);
+                Self::ReturnBookmark { bookmark, library }
+            }
+            Self::ReturnBookmark { bookmark, library } => {
+                if store.find(bookmark.id).is_some() {
+                    return Self::NONE;
+                }
+                let id = bookmark.id;
+                store.return_bookmark(bookmark, library);
+                Self::MoveBookmark { id, library }
+            }
+            Self::UpdateBookmark {
+                id,
+                title,
+                link,
+                note,
+            } => {
+                let Some(arena_id) = store.find(id) else {
+                    return Self::NONE;
+                };
+                let old = store.bookmark(arena_id);
+                let reverted = Self::UpdateBookmark {
+                    id,
+                    title: old.title.clone(),
+                    link: old.link.clone(),
+                    note: old.note.clone(),
+                };
+                if old.title == title && old.link == link && old.note == note {
+                    return Self::NONE;
+                }
+                store.update_bookmark(arena_id, &title, &link, &note, now);
+                reverted
+            }
+            Self::DeleteBookmark(id) => match store.find(id) {
+                Some(arena_id) if store.bookmark(arena_id).deleted_at.is_none() => {
+                    store.delete_bookmark(arena_id, now);
+                    Self::RestoreBookmark(id)
+                }
+                _ => Self::NONE,
+            },
+            Self::RestoreBookmark(id) => match store.find(id) {
+                Some(arena_id) if store.bookmark(arena_id).deleted_at.is_some() => {
+                    store.restore_bookmark(arena_id);
+                    Self::DeleteBookmark(id)
+                }
+                _ => Self::NONE,
+            },
+            Self::TagBookmark { id, tag } => {
+                let tag: Rc<str> = Rc::from(normalize_tag(&tag));
+                match store.find(id) {
+                    Some(arena_id)
+                        if !tag.is_empty() && !store.bookmark(arena_id).tags.contains(&tag) =>
+                    {
+                        store.tag_bookmark(arena_id, &tag, now);
+                        Self::UntagBookmark { id, tag }
+                    }
+                    _ => Self::NONE,
+                }
+            }
+            Self::UntagBookmark { id, tag } => {
+                let tag: Rc<str> = Rc::from(normalize_tag(&tag));
+                match store.find(id) {
+                    Some(arena_id) if store.bookmark(arena_id).tags.contains(&tag) => {
+                        store.untag_bookmark(arena_id, &tag, now);
+                        Self::TagBookmark { id, tag }
+                    }
+                    _ => Self::NONE,
+                }
+            }
+            Self::FileBookmark { id, collection } => {
+                let Some(arena_id) = store.find(id) else {
+                    return Self::NONE;
+                };
+                let target = collection.and_then(|x| store.find_collection(x));
+                let old = store.bookmark(arena_id).collection;
+                if (target.is_none() && collection.is_some()) || old == collection {
+                    return Self::NONE;
+                }
+                store.file_bookmark(arena_id, target, now);
+                Self::FileBookmark {
+                    id,
+                    collection: old,
+                }
+            }
+            Self::CreateCollection(collection) => {
+                if store.find_collection(collection.id).is_some() {
+                    return Self::NONE;
+                }
+                let id = collection.id;
+                store.create_collection(collection);
+                Self::RemoveCollection(id)
+            }
+            Self::RemoveCollection(id) => {
+                let Some(arena_id) = store.find_collection(id) else {
+                    return Self::NONE;
+                };
+                let collection = store.collection(arena_id).clone();
+                store.drop_collection(arena_id);
+                Self::CreateCollection(collection)
+            }
+            Self::MoveCollection { id, parent } => {
+                let Some(arena_id) = store.find_collection(id) else {
+                    return Self::NONE;
+                };
+                let target = parent.and_then(|x| store.find_collection(x));
+                if target.is_none() && parent.is_some() {
+                    return Self::NONE;
+                }
+                let old = store.collection(arena_id).parent;
+                store.move_collection(arena_id, target, now);
+                // Moving inside itself is refused
+                if store.collection(arena_id).parent == old {
+                    return Self::NONE;
+                }
+                Self::MoveCollection { id, parent: old }
+            }
+            Self::DeleteCollection(id) => {
+                let Some(arena_id) = store.find_collection(id) else {
+                    return Self::NONE;
+                };
+                let mut reverted = vec![Self::CreateCollection(store.collection(arena_id).clone())];
+                reverted.extend(
+                    store
+                        .all_collections()
+                        .filter(|x| x.parent == Some(id))
+                        .map(|x| Self::MoveCollection {
+                            id: x.id,
+                            parent: Some(id),
+                        }),
+                );
+                reverted.extend(
+                    store
+                        .bookmarks
+                        .iter()
+                        .filter(|x| x.value.collection == Some(id))
+                        .map(|x| Self::FileBookmark {
+                            id: x.value.id,
+                            collection: Some(id),
+                        }),
+                );
+                store.delete_collection(arena_id, now);
+                Self::Batch(reverted)
+            }
+            Self::Batch(commands) => {
+                let mut reverted: Vec<Command> = commands
+                    .into_iter()
+                    .map(|x| x.apply(store, now))
+                    .filter(|x| !x.is_none())
+                    .collect();
+                reverted.reverse();
+                Self::Batch(reverted)
+            }
+        }
+    }
+}
+
+/// Reverting commands of the edits made, and of the undos to redo them
+#[derive(Default)]
+pub struct UndoStack {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+}
+
+impl UndoStack {
+    /// Applies the commands as one undo step. A new edit can't be redone over, so redo is
+    /// cleared unless nothing changed.
+    pub fn apply(&mut self, store: &mut Store, commands: Vec<Command>, now: Timestamp) {
+        let reverted = Command::Batch(commands).apply(store, now);
+        if reverted.is_none() {
+            return;
+        }
+
+        self.redo.clear();
+        self.undo.push(reverted);
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+    pub fn undo(&mut self, store: &mut Store, now: Timestamp) {
+        if let Some(command) = self.undo.pop() {
+            self.redo.push(command.apply(store, now));
+        }
+    }
+    pub fn redo(&mut self, store: &mut Store, now: Timestamp) {
+        if let Some(command) = self.redo.pop() {
+            self.undo.push(command.apply(store, now));
+        }
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, UndoStack};
+    use crate::{
+        library::Libraries,
+        store::{Bookmark, Collection, SortOrder, Store, Transfer},
+    };
+
+    #[test]
+    fn undoes_and_redoes() {
+        let mut store = Store::new();
+        let mut history = UndoStack::default();
+
+        let bookmark = Bookmark::new("Rust", "https://www.rust-lang.org/", "", 1);
+        let id = bookmark.id;
+        history.apply(
+            &mut store,
+            vec![
+                Command::CreateBookmark(bookmark),
+                Command::TagBookmark {
+                    id,
+                    tag: "Lang".into(),
+                },
+            ],
+            1,
+        );
+
+        let collection = Collection::new("Languages", None, 2);
+        let collection_id = collection.id;
+        history.apply(
+            &mut store,
+            vec![
+                Command::CreateCollection(collection),
+                Command::FileBookmark {
+                    id,
+                    collection: Some(collection_id),
+                },
+            ],
+            2,
+        );
+        history.apply(
+            &mut store,
+            vec![Command::DeleteCollection(collection_id)],
+            3,
+        );
+        history.apply(&mut store, vec![Command::DeleteBookmark(id)], 4);
+
+        let snapshot = |store: &mut Store| {
+            let bookmark = store.find(id).map(|x| store.bookmark(x).clone());
+            let tags = store.tags().len();
+            let live = store.search("", None, SortOrder::Newest).len();
+            (
+                bookmark,
+                tags,
+                live,
+                store.find_collection(collection_id).is_some(),
+            )
+        };
+        let deleted = snapshot(&mut store);
+        assert_eq!(deleted.2, 0);
+
+        history.undo(&mut store, 5);
+        history.undo(&mut store, 5);
+        let filed = snapshot(&mut store);
+        assert_eq!(filed.0.as_ref().unwrap().collection, Some(collection_id));
+        assert_eq!(filed.0.as_ref().unwrap().tags, vec!["lang".into()]);
+        assert!(filed.3);
+
+        history.undo(&mut store, 5);
+        history.undo(&mut store, 5);
+        assert!(!history.can_undo());
+        assert_eq!(snapshot(&mut store), (None, 0, 0, false));
+
+        for _ in 0..4 {
+            history.redo(&mut store, 6);
+        }
+        assert!(!history.can_redo());
+        let redone = snapshot(&mut store);
+        assert_eq!(redone.2, 0);
+        assert_eq!(redone.0.as_ref().unwrap().collection, None);
+        assert!(!redone.3);
+
+        // Changes are persisted like any other edit
+        assert!(store.changes().is_some());
+        assert!(store.collection_removals().is_some());
+    }
+
+    #[test]
+    fn changing_nothing_is_not_a_step() {
+        let mut store = Store::new();
+        let mut history = UndoStack::default();
+
+        let bookmark = Bookmark::new("Rust", "https://www.rust-lang.org/", "", 1);
+        let id = bookmark.id;
+        history.apply(&mut store, vec![Command::CreateBookmark(bookmark)], 1);
+        history.undo(&mut store, 2);
+
+        // Gone, so there is nothing to tag and redo is kept
+        history.apply(
+            &mut store,
+            vec![Command::TagBookmark {
+                id,
+                tag: "rust".into(),
+            }],
+            3,
+        );
+        assert!(history.can_redo());
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn removes_a_tag_from_every_bookmark() {
+        let mut store = Store::new();
+        let mut history = UndoStack::default();
+
+        let rust = store.create_bookmark(
+            Bookmark::new("Rust", "https://www.rust-lang.org/", "", 1).with_tags(&["lang"]),
+        );
+        let go = store
+            .create_bookmark(Bookmark::new("Go", "https://go.dev/", "", 1).with_tags(&["lang"]));
+        store.delete_bookmark(go, 2);
+
+        let commands = store.remove_tag("Lang");
+        assert_eq!(commands.len(), 2);
+        history.apply(&mut store, commands, 3);
+        assert!(store.bookmark(rust).tags.is_empty());
+        assert!(store.bookmark(go).tags.is_empty());
+        assert_eq!(store.bookmark(rust).updated_at, 3);
+        assert!(store.tags().is_empty());
+
+        history.undo(&mut store, 4);
+        assert_eq!(store.bookmark(rust).tags, vec!["lang".into()]);
+        assert_eq!(store.bookmark(go).tags, vec!["lang".into()]);
+        assert_eq!(store.tags(), vec![("lang".into(), 1)]);
+
+        // Typed the way it was tagged
+        let id = store.bookmark(rust).id;
+        history.apply(
+            &mut store,
+            vec![Command::UntagBookmark {
+                id,
+                tag: "Lang".into(),
+            }],
+            5,
+        );
+        assert!(store.bookmark(rust).tags.is_empty());
+    }
+
+    #[test]
+    fn undoing_a_move_takes_the_bookmark_back() {
+        let mut store = Store::new();
+        let mut history = UndoStack::default();
+        let library = Libraries::default().create("Work");
+
+        let bookmark = Bookmark::new("Rust", "https://www.rust-lang.org/", "", 1);
+        let id = bookmark.id;
+        store.create_bookmark(bookmark.clone());
+        store.changes();
+
+        history.apply(&mut store, vec![Command::MoveBookmark { id, library }], 2);
+        assert_eq!(store.find(id), None);
+        assert_eq!(store.removals().unwrap().collect::<Vec<_>>(), vec![id]);
+        assert_eq!(
+            store.transfers().unwrap().collect::<Vec<_>>(),
+            vec![Transfer::Out(bookmark.clone(), library)]
+        );
+
+        history.undo(&mut store, 3);
+        let returned = store.find(id).unwrap();
+        assert_eq!(store.bookmark(returned), &bookmark);
+        assert!(store.removals().is_none());
+        assert_eq!(
+            store.transfers().unwrap().collect::<Vec<_>>(),
+            vec![Transfer::In(id, library)]
+        );
+
+        history.redo(&mut store, 4);
+        assert_eq!(store.find(id), None);
+        assert_eq!(
+            store.transfers().unwrap().collect::<Vec<_>>(),
+            vec![Transfer::Out(bookmark, library)]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use wasm_bindgen::{prelude::*, JsValue};
+
+/// Milliseconds since unix epoch
+pub type Timestamp = u64;
+
+pub const SECOND: Timestamp = 1000;
+pub const MINUTE: Timestamp = SECOND * 60;
+pub const HOUR: Timestamp = MINUTE * 60;
+pub const DAY: Timestamp = HOUR * 24;
+
+pub fn now() -> Timestamp {
+    js_sys::Date::now() as Timestamp
+}
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(handler: &js_sys::Function, timeout: i32) -> JsValue;
+}
+
+/// Resolves after `duration` milliseconds
+pub async fn sleep(duration: Timestamp) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        set_timeout(&resolve, duration as i32);
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Formats with the user's locale
+pub fn format(timestamp: Timestamp) -> String {
+    let date = js_sys::Date::new(&JsValue::from_f64(timestamp as f64));
+    date.to_locale_string("default", &JsValue::UNDEFINED).into()
+}
+
+/// Reads `2024-06-01T12:00:00Z` like dates, fraction of seconds and offset are optional. Without an
+/// offset it's UTC. A space can separate date and time.
+pub fn parse_iso8601(text: &str) -> Option<Timestamp> {
+    let text = text.trim();
+    let (date, time) = text.split_once(['T', ' ']).unwrap_or((text, "00:00:00"));
+
+    let mut date_parts = date.splitn(3, '-').map(|x| x.parse::<i64>().ok());
+    let (year, month, day) = (
+        date_parts.next()??,
+        date_parts.next()??,
+        date_parts.next()??,
+    );
+
+    let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(index) => time.split_at(index),
+        None => (time, ""),
+    };
+    let offset = match offset {
+        "" | "Z" | "z" => 0,
+        offset => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let (hours, minutes) = offset[1..].split_once(':').unwrap_or((&offset[1..], "0"));
+            sign * (hours.parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?) * 60
+        }
+    };
+
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut time_parts = time.splitn(3, ':').map(|x| x.parse::<i64>().ok());
+    let hours = time_parts.next()??;
+    let minutes = time_parts.next().unwrap_or(Some(0))?;
+    let seconds = time_parts.next().unwrap_or(Some(0))?;
+    let millis = format!("{:0<3}", fraction.get(..3).unwrap_or(fraction))
+        .parse::<i64>()
+        .ok()?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds = days * 86400 + hours * 3600 + minutes * 60 + seconds - offset;
+    Timestamp::try_from(seconds * 1000 + millis).ok()
+}
+
+/// Writes `2024-06-01T12:00:00.000Z`, what [`parse_iso8601`] reads
+pub fn format_iso8601(timestamp: Timestamp) -> String {
+    let days = (timestamp / DAY) as i64;
+    let millis = timestamp % DAY;
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        millis / HOUR,
+        millis % HOUR / MINUTE,
+        millis % MINUTE / SECOND,
+        millis % SECOND
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_iso8601, parse_iso8601};
+
+    #[test]
+    fn parses_iso8601() {
+        assert_eq!(parse_iso8601("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_iso8601("2024-06-01T12:00:00Z"), Some(1717243200000));
+        assert_eq!(
+            parse_iso8601("2024-06-01T12:00:00.250Z"),
+            Some(1717243200250)
+        );
+        assert_eq!(
+            parse_iso8601("2024-06-01 14:00:00+02:00"),
+            Some(1717243200000)
+        );
+        assert_eq!(
+            parse_iso8601("2024-06-01T07:30:00-04:30"),
+            Some(1717243200000)
+        );
+        assert_eq!(parse_iso8601("2024-06-01"), Some(1717200000000));
+        assert_eq!(parse_iso8601("2024-02-29T00:00:00Z"), Some(1709164800000));
+        assert_eq!(parse_iso8601("yesterday"), None);
+        assert_eq!(parse_iso8601("2024-13-01"), None);
+    }
+
+    #[test]
+    fn formats_iso8601() {
+        assert_eq!(format_iso8601(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(format_iso8601(1717243200250), "2024-06-01T12:00:00.250Z");
+        assert_eq!(format_iso8601(1709164800000), "2024-02-29T00:00:00.000Z");
+
+        for timestamp in [951782400000, 1735689599999, 4102444800000] {
+            assert_eq!(parse_iso8601(&format_iso8601(timestamp)), Some(timestamp));
+        }
+    }
+}
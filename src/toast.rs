@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dioxus::prelude::*;
+
+use crate::{
+    button::{Button, ButtonSize},
+    Action,
+};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone)]
+pub struct Toast {
+    id: u64,
+    message: String,
+    /// Sent back to the coroutine when retry is clicked
+    retry: Option<Action>,
+}
+
+/// Shows a toast until it is dismissed
+pub fn notify(mut toasts: Signal<Vec<Toast>>, message: String, retry: Option<Action>) {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    toasts.write().push(Toast { id, message, retry });
+}
+
+#[component]
+pub fn Toasts(toasts: Signal<Vec<Toast>>) -> Element {
+    let coroutine = use_coroutine_handle::<Action>();
+
+    let mut toasts = toasts;
+    let mut dismiss = move |id: u64| toasts.write().retain(|x| x.id != id);
+
+    rsx! {
+        div {
+            class: "fixed bottom-0 right-0 p-8 flex flex-col gap-4 w-96",
+            for Toast { id, message, retry } in toasts() {
+                div {
+                    key: "{id}",
+                    class: "flex flex-col gap-2 px-4 py-2 bg-primary shadow-md rounded-xl border border-gray-200",
+                    span {
+                        class: "text-sm break-words",
+                        "{message}"
+                    }
+                    div {
+                        class: "flex gap-2 justify-end",
+                        if let Some(retry) = retry {
+                            Button {
+                                value: "Retry",
+                                size: ButtonSize::Small,
+                                onclick: move |_| {
+                                    coroutine.send(retry.clone());
+                                    dismiss(id);
+                                },
+                            }
+                        }
+                        Button {
+                            value: "Dismiss",
+                            size: ButtonSize::Small,
+                            onclick: move |_| dismiss(id),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
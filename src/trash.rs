@@ -0,0 +1,70 @@
+use dioxus::prelude::*;
+
+use crate::{
+    arena::ArenaId,
+    button::{Button, ButtonSize},
+    store::Bookmark,
+    time, Action,
+};
+
+#[component]
+pub fn Trash(bookmarks: Vec<(ArenaId<Bookmark>, Bookmark)>, retention_days: u32) -> Element {
+    let coroutine = use_coroutine_handle::<Action>();
+
+    rsx! {
+        div {
+            class: "flex-1 w-full flex flex-col p-8 gap-8",
+            div {
+                class: "flex items-center gap-4",
+                span { "Keep deleted bookmarks for" }
+                input {
+                    class: "bg-primary w-24 px-4 h-8 rounded-md border border-gray-300",
+                    r#type: "number",
+                    min: "0",
+                    value: "{retention_days}",
+                    onchange: move |ev| {
+                        if let Ok(days) = ev.value().parse::<u32>() {
+                            coroutine.send(Action::SetTrashRetention { days });
+                        }
+                    },
+                }
+                span { "days" }
+            }
+            if bookmarks.is_empty() {
+                span {
+                    class: "text-gray-500",
+                    "Trash is empty"
+                }
+            }
+            div {
+                class: "w-full grid grid-cols-cards grid-rows-[min-content] gap-8",
+                for (id, bookmark) in bookmarks {
+                    div {
+                        key: "{bookmark.id}",
+                        class: "flex flex-col gap-2 px-4 py-2 bg-primary shadow-md rounded-xl break-words",
+                        h3 {
+                            class: "text-sm font-bold",
+                            {bookmark.title.to_string()}
+                        }
+                        span {
+                            class: "text-gray-500 break-words",
+                            {bookmark.link.to_string()}
+                        }
+                        div {
+                            class: "flex items-center gap-2",
+                            span {
+                                class: "flex-1 text-xs text-gray-500",
+                                "Deleted {time::format(bookmark.deleted_at.unwrap_or_default())}"
+                            }
+                            Button {
+                                value: "Restore",
+                                size: ButtonSize::Small,
+                                onclick: move |_| coroutine.send(Action::RestoreBookmark { id }),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}